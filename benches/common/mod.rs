@@ -0,0 +1,81 @@
+use std::{fmt::Write, path::PathBuf};
+
+const CONFIG: &str = r#"arrayLayout: List<{type}>
+modelFileName: "{{object_name | snake_case}}.dart"
+types:
+    String:
+        default: String
+        format:
+            Date: DateTime
+            DateTime: DateTime
+    Number:
+        default: double
+        format:
+            Float: double
+            Double: double
+    Integer:
+        default: int
+        format:
+            Int32: int
+            Int64: int
+    Boolean:
+        default: bool
+"#;
+
+const MODEL_TEMPLATE: &str = r#"class {{object_name}} {
+{% for field in properties %}    final {% if field.property_type == "Array" %}List<{{field.object_name}}>{% elif field.property_type == "Object" %}{{field.object_name}}{% else %}{{map_type(type = field)}}{% endif %}{% if not field.required %}?{% endif %} {{field.name | mixed_case}};
+{% endfor %}
+    {{object_name}}({ {% for field in properties %}{% if field.required %}required {% endif %}this.{{field.name | mixed_case}}, {% endfor %}});
+}
+"#;
+
+const SERVICE_TEMPLATE: &str = r#"class Service {
+{% for endpoint in endpoints %}    // {{endpoint.method | upper_case}} {{endpoint.path}}
+    Future<void> {{endpoint.method}}{{endpoint.path | camel_case}}() async {}
+{% endfor %}}
+"#;
+
+/// Builds a spec with an endpoint per model so every model is rendered
+pub fn synthetic_spec(models: usize, properties: usize) -> String {
+    let types = ["string", "number", "integer", "boolean"];
+    let mut spec = String::from(
+        "openapi: 3.0.0\ninfo:\n  title: Benchmark\n  version: '1'\nservers:\n  - url: https://example.com\npaths:\n",
+    );
+    for model in 0..models {
+        writeln!(
+            spec,
+            "  /resource{model}:\n    post:\n      requestBody:\n        content:\n          application/json:\n            schema:\n              $ref: '#/components/schemas/Model{model}'\n      responses:\n        '200':\n          description: OK\n          content:\n            application/json:\n              schema:\n                $ref: '#/components/schemas/Model{model}'"
+        )
+        .unwrap();
+    }
+    spec.push_str("components:\n  schemas:\n");
+    for model in 0..models {
+        writeln!(
+            spec,
+            "    Model{model}:\n      type: object\n      required: [property0]\n      properties:"
+        )
+        .unwrap();
+        for property in 0..properties {
+            writeln!(
+                spec,
+                "        property{property}:\n          type: {}",
+                types[property % types.len()]
+            )
+            .unwrap();
+        }
+    }
+    spec
+}
+
+/// Creates a project in the temp folder with a config, templates and the spec saved as `api.yaml`
+pub fn setup_project(name: &str, spec: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("api-gen-bench-{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("templates")).unwrap();
+    std::fs::create_dir_all(dir.join("output")).unwrap();
+    std::fs::write(dir.join("config.yaml"), CONFIG).unwrap();
+    std::fs::write(dir.join("templates").join("model.dart"), MODEL_TEMPLATE).unwrap();
+    std::fs::write(dir.join("templates").join("service.dart"), SERVICE_TEMPLATE).unwrap();
+    std::fs::write(dir.join("api.yaml"), spec).unwrap();
+    dir
+}
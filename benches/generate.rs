@@ -0,0 +1,99 @@
+mod common;
+
+use std::path::PathBuf;
+
+use api_gen::{
+    cache::Cache, generate, get_open_api_content_and_doc, serde_method::serde_openapi, GenerateArgs,
+};
+use common::{setup_project, synthetic_spec};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+const PETSTORE: &str = include_str!("fixtures/petstore.yaml");
+
+fn fixtures() -> Vec<(&'static str, PathBuf)> {
+    vec![
+        ("petstore", setup_project("petstore", PETSTORE)),
+        (
+            "synthetic_1000",
+            setup_project("synthetic-1000", &synthetic_spec(1000, 10)),
+        ),
+    ]
+}
+
+fn open_api_content(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_open_api_content_and_doc");
+    group.sample_size(10);
+    for (name, dir) in fixtures() {
+        let api = dir.join("api.yaml");
+        group.bench_function(name, |b| {
+            b.iter(|| get_open_api_content_and_doc(&api, &mut Cache::disabled()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn extract(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serde_openapi");
+    group.sample_size(10);
+    for (name, dir) in fixtures() {
+        let contents = std::fs::read_to_string(dir.join("api.yaml")).unwrap();
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || contents.clone(),
+                |contents| serde_openapi(contents).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn flatten(c: &mut Criterion) {
+    let mut group = c.benchmark_group("flatten");
+    group.sample_size(10);
+    for (name, dir) in fixtures() {
+        let contents = std::fs::read_to_string(dir.join("api.yaml")).unwrap();
+        let template = serde_openapi(contents).unwrap();
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || template.clone(),
+                |mut template| template.flatten(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn full_generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+    group.sample_size(10);
+    let cwd = std::env::current_dir().unwrap();
+    for (name, dir) in fixtures() {
+        // The templates and config are looked up relative to the current directory
+        std::env::set_current_dir(&dir).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                generate(GenerateArgs {
+                    api: PathBuf::from("api.yaml"),
+                    output: Some(PathBuf::from("output")),
+                    config: None,
+                    verbose: false,
+                    quiet: true,
+                    sequential: false,
+                    no_cache: true,
+                    jobs: None,
+                    set: Vec::new(),
+                    report_format: None,
+                    report: None,
+                })
+                .unwrap()
+            })
+        });
+    }
+    std::env::set_current_dir(cwd).unwrap();
+    group.finish();
+}
+
+criterion_group!(benches, open_api_content, extract, flatten, full_generate);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+mod common;
+
+use std::{path::PathBuf, process::Command};
+
+use common::{setup_project, synthetic_spec};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+fn generate(dir: &PathBuf, jobs: usize) {
+    let status = Command::new(env!("CARGO_BIN_EXE_api-gen"))
+        .current_dir(dir)
+        .args(["generate", "--api", "api.yaml", "--output", "output"])
+        .args(["--no-cache", "--quiet", "--jobs", &jobs.to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+fn render_models(c: &mut Criterion) {
+    let dir = setup_project("render", &synthetic_spec(500, 20));
+    let cores = std::thread::available_parallelism()
+        .map(|x| x.get())
+        .unwrap_or(1);
+    let mut group = c.benchmark_group("render_models");
+    group.sample_size(10);
+    let mut jobs_to_compare = vec![1];
+    if cores > 1 {
+        jobs_to_compare.push(cores);
+    }
+    for jobs in jobs_to_compare {
+        group.bench_with_input(BenchmarkId::from_parameter(jobs), &jobs, |b, jobs| {
+            b.iter(|| generate(&dir, *jobs))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render_models);
+criterion_main!(benches);
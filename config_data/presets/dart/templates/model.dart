@@ -0,0 +1,26 @@
+{# This an example model template, a file named with `modelFileName` is generated for each model #}
+import 'package:json_annotation/json_annotation.dart';
+{% for import in imports %}import '{{ import }}';
+{% endfor %}
+part "{{file_name | replace(from=".dart", to=".g.dart")}}";
+
+@JsonSerializable()
+class {{object_name}} {
+    {% for field in properties -%}
+    @JsonKey(name: '{{field.wire_name}}')
+    final {{field.mapped_type}} {{field.name | field_name}};
+    {% endfor %}
+
+    {{object_name}}(
+        {%- if properties -%}
+        { {% for field in properties -%}{% if field.required %}
+        required this.{{field.name | field_name}},{% else %}
+        this.{{field.name | field_name}},{% endif %}{% endfor %}
+    }
+    {%- endif -%}
+    );
+
+    factory {{object_name}}.fromJson(Map<String, dynamic> json) => _${{object_name}}FromJson(json);
+
+    Map<String, dynamic> toJson() => _${{object_name}}ToJson(this);
+}
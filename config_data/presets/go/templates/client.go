@@ -0,0 +1,72 @@
+package {{ extended(key="package") }}
+
+import (
+	"bytes"
+	"context"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net/http"
+)
+
+// Client for the endpoints of the API
+type Client struct {
+	BaseURL    string
+	HTTPClient *http.Client
+}
+{% for endpoint in endpoints %}
+{%- set_global url = '"' ~ endpoint.path ~ '"' %}
+{%- set_global arguments = ["ctx context.Context"] %}
+{%- for parameter in endpoint.parameters | filter(attribute="in", value="path") %}
+{%- set name = parameter.name | mixed_case %}
+{%- set_global url = url | replace(from="{" ~ parameter.name ~ "}", to='" + fmt.Sprint(' ~ name ~ ') + "') %}
+{%- set_global arguments = arguments | concat(with=name ~ " any") %}
+{%- endfor %}
+{%- set_global url = url | replace(from=' + ""', to="") %}
+{%- if endpoint.request %}
+{%- set_global arguments = arguments | concat(with="request " ~ endpoint.request.mapped_type) %}
+{%- endif %}
+{%- set method = endpoint.method | camel_case %}
+{%- set function = method ~ endpoint.path | camel_case %}
+// {{ function }} {% if endpoint.description %}{{ endpoint.description }}{% else %}calls {{ endpoint.method | upper }} {{ endpoint.path }}{% endif %}
+{%- if endpoint.response %}
+func (c *Client) {{ function }}({{ arguments | join(sep=", ") }}) ({{ endpoint.response.mapped_type }}, error) {
+	var response {{ endpoint.response.mapped_type }}
+	err := c.do(ctx, "{{ endpoint.method | upper }}", {{ url }}, {% if endpoint.request %}request{% else %}nil{% endif %}, &response)
+	return response, err
+}
+{%- else %}
+func (c *Client) {{ function }}({{ arguments | join(sep=", ") }}) error {
+	return c.do(ctx, "{{ endpoint.method | upper }}", {{ url }}, {% if endpoint.request %}request{% else %}nil{% endif %}, nil)
+}
+{%- endif %}
+{% endfor %}
+func (c *Client) do(ctx context.Context, method, path string, request, response any) error {
+	var body io.Reader
+	if request != nil {
+		data, err := json.Marshal(request)
+		if err != nil {
+			return err
+		}
+		body = bytes.NewReader(data)
+	}
+	req, err := http.NewRequestWithContext(ctx, method, c.BaseURL+path, body)
+	if err != nil {
+		return err
+	}
+	if request != nil {
+		req.Header.Set("Content-Type", "application/json")
+	}
+	res, err := c.HTTPClient.Do(req)
+	if err != nil {
+		return err
+	}
+	defer res.Body.Close()
+	if res.StatusCode >= 300 {
+		return fmt.Errorf("%s %s: %s", method, path, res.Status)
+	}
+	if response == nil {
+		return nil
+	}
+	return json.NewDecoder(res.Body).Decode(response)
+}
@@ -0,0 +1,16 @@
+{# A struct for each model, in a file named with `modelFileName`. The models are in the same package so only the other imports are written #}
+package {{ extended(key="package") }}
+{% for import in imports -%}
+{% if import is not ending_with(".go") %}
+import "{{ import }}"
+{% endif -%}
+{% endfor %}
+{% if description %}// {{ object_name }} {{ description }}
+{% endif -%}
+type {{ object_name }} struct {
+{%- for field in properties %}
+	{% if field.description %}// {{ field.description }}
+	{% endif -%}
+	{{ field.name | field_name }} {{ field.mapped_type }} `json:"{{ field.wire_name }}{% if not field.required %},omitempty{% endif %}"`
+{%- endfor %}
+}
@@ -0,0 +1,34 @@
+# A scenario for each endpoint, sending a request built with `json_value` and matching the response with `json_typing`
+Feature: API
+
+  Background:
+{%- if base_url %}
+    * url '{{ base_url | first }}'
+{%- else %}
+    * url baseUrl
+{%- endif %}
+{% for endpoint in endpoints %}
+  {%- set_global url = "'" ~ endpoint.path ~ "'" %}
+  {%- for parameter in endpoint.parameters | filter(attribute="in", value="path") %}
+  {%- set_global url = url | replace(from="{" ~ parameter.name ~ "}", to="' + " ~ parameter.name ~ " + '") %}
+  {%- endfor %}
+  {%- set_global url = url | replace(from=" + ''", to="") %}
+  Scenario: {% if endpoint.description %}{{ endpoint.description }}{% else %}{{ endpoint.method | upper }} {{ endpoint.path }}{% endif %}
+  {%- for parameter in endpoint.parameters | filter(attribute="in", value="path") %}
+    * def {{ parameter.name }} = 1
+  {%- endfor %}
+    Given path {{ url }}
+  {%- if endpoint.request %}
+    And request {{ json_value(structure=endpoint.request) | json_encode() }}
+  {%- endif %}
+    When method {{ endpoint.method }}
+    Then status 200
+  {%- if endpoint.response %}
+  {%- set typing = json_typing(structure=endpoint.response) %}
+  {%- if endpoint.response.property_type == "Array" %}
+    And match each response == {{ typing | first | json_encode() }}
+  {%- else %}
+    And match response == {{ typing | json_encode() }}
+  {%- endif %}
+  {%- endif %}
+{% endfor -%}
@@ -0,0 +1,35 @@
+import io.ktor.client.HttpClient
+import io.ktor.client.call.body
+import io.ktor.client.request.request
+import io.ktor.client.request.setBody
+import io.ktor.http.ContentType
+import io.ktor.http.HttpMethod
+import io.ktor.http.contentType
+
+/** Client for the endpoints of the API, the HttpClient needs the ContentNegotiation plugin with JSON */
+class ApiClient(private val client: HttpClient, private val baseUrl: String) {
+{%- for endpoint in endpoints %}
+    {%- set_global url = endpoint.path %}
+    {%- set_global arguments = [] %}
+    {%- for parameter in endpoint.parameters | filter(attribute="in", value="path") %}
+    {%- set name = parameter.name | field_name %}
+    {%- set_global url = url | replace(from="{" ~ parameter.name ~ "}", to="${" ~ name ~ "}") %}
+    {%- set_global arguments = arguments | concat(with=name ~ ": Any") %}
+    {%- endfor %}
+    {%- if endpoint.request %}
+    {%- set_global arguments = arguments | concat(with="request: " ~ endpoint.request.mapped_type) %}
+    {%- endif %}
+
+    {% if endpoint.description %}/** {{ endpoint.description }} */
+    {% endif -%}
+    suspend fun {{ endpoint.method | lower }}{{ endpoint.path | camel_case }}({{ arguments | join(sep=", ") }}){% if endpoint.response %}: {{ endpoint.response.mapped_type }}{% endif %} {
+        {% if endpoint.response %}return {% endif %}client.request("$baseUrl{{ url }}") {
+            method = HttpMethod.parse("{{ endpoint.method | upper }}")
+            {%- if endpoint.request %}
+            contentType(ContentType.Application.Json)
+            setBody(request)
+            {%- endif %}
+        }{% if endpoint.response %}.body(){% endif %}
+    }
+{%- endfor %}
+}
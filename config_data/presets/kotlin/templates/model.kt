@@ -0,0 +1,20 @@
+{# A data class for each model, in a file named with `modelFileName`. The models are in the same package so only the other imports are written #}
+{%- for import in imports -%}
+{% if import is not ending_with(".kt") -%}
+import {{ import }}
+{% endif -%}
+{% endfor -%}
+import kotlinx.serialization.SerialName
+import kotlinx.serialization.Serializable
+
+{% if description %}/** {{ description }} */
+{% endif -%}
+@Serializable
+data class {{ object_name }}(
+{%- for field in properties %}
+    {% if field.description %}/** {{ field.description }} */
+    {% endif -%}
+    @SerialName("{{ field.wire_name }}")
+    val {{ field.name | field_name }}: {{ field.mapped_type }}{% if not field.required %} = null{% endif %},
+{%- endfor %}
+)
@@ -0,0 +1,55 @@
+{# A requests client for the endpoints #}
+{%- set_global names = [] -%}
+{%- for endpoint in endpoints -%}
+{%- for model in [endpoint.request, endpoint.response] -%}
+{%- if model and model.object_name and model.object_name not in names -%}
+{%- set_global names = names | concat(with=model.object_name) -%}
+{%- endif -%}
+{%- endfor -%}
+{%- endfor -%}
+from __future__ import annotations
+
+import datetime
+from typing import Any, List, Optional
+
+import requests
+from pydantic import TypeAdapter
+
+{% for name in names | sort -%}
+from .{{ name | snake_case }} import {{ name }}
+{% endfor %}
+
+class Client:
+    """Client for the endpoints of the API"""
+
+    def __init__(self, base_url: str, session: Optional[requests.Session] = None):
+        self.base_url = base_url
+        self.session = session or requests.Session()
+{% for endpoint in endpoints %}
+    {%- set_global url = endpoint.path %}
+    {%- set_global arguments = "" %}
+    {%- for parameter in endpoint.parameters | filter(attribute="in", value="path") %}
+    {%- set name = parameter.name | field_name %}
+    {%- set_global url = url | replace(from="{" ~ parameter.name ~ "}", to="{" ~ name ~ "}") %}
+    {%- set_global arguments = arguments ~ ", " ~ name ~ ": Any" %}
+    {%- endfor %}
+    {%- if endpoint.request %}
+    {%- set_global arguments = arguments ~ ", request: " ~ endpoint.request.mapped_type %}
+    {%- endif %}
+    def {{ endpoint.method | lower }}_{{ endpoint.path | snake_case }}(self{{ arguments }}) -> {% if endpoint.response %}{{ endpoint.response.mapped_type }}{% else %}None{% endif %}:
+        {% if endpoint.description %}"""{{ endpoint.description }}"""
+        {% endif -%}
+        response = self.session.request(
+            "{{ endpoint.method | upper }}",
+            f"{self.base_url}{{ url }}",
+            {%- if endpoint.request %}
+            json=TypeAdapter({{ endpoint.request.mapped_type }}).dump_python(
+                request, mode="json", by_alias=True, exclude_none=True
+            ),
+            {%- endif %}
+        )
+        response.raise_for_status()
+        {%- if endpoint.response %}
+        return TypeAdapter({{ endpoint.response.mapped_type }}).validate_python(response.json())
+        {%- endif %}
+{% endfor -%}
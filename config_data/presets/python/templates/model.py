@@ -0,0 +1,26 @@
+{# A Pydantic model for each model, in a file named with `modelFileName` #}
+from __future__ import annotations
+
+{% for import in imports -%}
+{% if import is ending_with(".py") -%}
+from .{{ import | replace(from=".py", to="") }} import *
+{% else -%}
+import {{ import }}
+{% endif -%}
+{% endfor -%}
+from typing import List, Optional
+
+from pydantic import BaseModel, ConfigDict, Field
+
+
+class {{ object_name }}(BaseModel):
+    {% if description %}"""{{ description }}"""
+
+    {% endif -%}
+    model_config = ConfigDict(populate_by_name=True)
+{% for field in properties %}
+    {{ field.name | field_name }}: {{ field.mapped_type }} = Field({% if not field.required %}default=None, {% endif %}alias="{{ field.wire_name }}")
+{%- if field.description %}
+    """{{ field.description }}"""
+{%- endif %}
+{%- endfor %}
@@ -0,0 +1,57 @@
+{# Declares the module of each model generated with `model.rs` and a reqwest client for the endpoints #}
+{%- set_global names = [] -%}
+{%- for model in requests | concat(with=responses) -%}
+{%- if model.property_type == "Object" and model.object_name not in names -%}
+{%- set_global names = names | concat(with=model.object_name) -%}
+{%- endif -%}
+{%- endfor -%}
+{%- for name in names | sort -%}
+mod {{ name | snake_case }};
+pub use {{ name | snake_case }}::*;
+{% endfor %}
+/// Client for the endpoints of the API
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl Client {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into(),
+        }
+    }
+{% for endpoint in endpoints %}
+    {%- set_global url = endpoint.path %}
+    {% if endpoint.description %}/// {{ endpoint.description }}
+    {% endif -%}
+    pub async fn {{ endpoint.method | lower }}_{{ endpoint.path | snake_case }}(
+        &self,
+        {%- for parameter in endpoint.parameters | filter(attribute="in", value="path") %}
+        {%- set name = parameter.name | field_name %}
+        {%- set_global url = url | replace(from="{" ~ parameter.name ~ "}", to="{" ~ name ~ "}") %}
+        {{ name }}: impl std::fmt::Display,
+        {%- endfor %}
+        {%- if endpoint.request %}
+        request: &{{ endpoint.request.mapped_type }},
+        {%- endif %}
+    ) -> reqwest::Result<{% if endpoint.response %}{{ endpoint.response.mapped_type }}{% else %}(){% endif %}> {
+        let response = self
+            .http
+            .{{ endpoint.method | lower }}(format!("{}{{ url }}", self.base_url))
+            {%- if endpoint.request %}
+            .json(request)
+            {%- endif %}
+            .send()
+            .await?
+            .error_for_status()?;
+        {%- if endpoint.response %}
+        response.json().await
+        {%- else %}
+        drop(response);
+        Ok(())
+        {%- endif %}
+    }
+{% endfor -%}
+}
@@ -0,0 +1,21 @@
+{# A serde struct for each model, in a file named with `modelFileName` #}
+{%- for import in imports -%}
+{% if import is ending_with(".rs") -%}
+use super::{{ import | replace(from=".rs", to="") }}::*;
+{% else -%}
+use {{ import }};
+{% endif -%}
+{% endfor -%}
+use serde::{Deserialize, Serialize};
+
+{% if description %}/// {{ description }}
+{% endif -%}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct {{ object_name }} {
+{%- for field in properties %}
+    {% if field.description %}/// {{ field.description }}
+    {% endif -%}
+    #[serde(rename = "{{ field.wire_name }}"{% if not field.required %}, default, skip_serializing_if = "Option::is_none"{% endif %})]
+    pub {{ field.name | field_name }}: {{ field.mapped_type }},
+{%- endfor %}
+}
@@ -0,0 +1,48 @@
+{# A fetch client for the endpoints #}
+{%- set_global names = [] -%}
+{%- for endpoint in endpoints -%}
+{%- for model in [endpoint.request, endpoint.response] -%}
+{%- if model and model.object_name and model.object_name not in names -%}
+{%- set_global names = names | concat(with=model.object_name) -%}
+{%- endif -%}
+{%- endfor -%}
+{%- endfor -%}
+{%- for name in names | sort -%}
+import type { {{ name }} } from './{{ name }}';
+{% endfor %}
+/** Client for the endpoints of the API */
+export class Client {
+    constructor(
+        private readonly baseUrl: string,
+        private readonly headers: Record<string, string> = {},
+    ) {}
+{% for endpoint in endpoints %}
+    {%- set_global url = endpoint.path %}
+    {% if endpoint.description %}/** {{ endpoint.description }} */
+    {% endif -%}
+    async {{ endpoint.method | lower }}{{ endpoint.path | camel_case }}(
+        {%- for parameter in endpoint.parameters | filter(attribute="in", value="path") %}
+        {%- set name = parameter.name | field_name %}
+        {%- set_global url = url | replace(from="{" ~ parameter.name ~ "}", to="${encodeURIComponent(" ~ name ~ ")}") %}
+        {{ name }}: string | number,
+        {%- endfor %}
+        {%- if endpoint.request %}
+        request: {{ endpoint.request.mapped_type }},
+        {%- endif %}
+    ): Promise<{% if endpoint.response %}{{ endpoint.response.mapped_type }}{% else %}void{% endif %}> {
+        const response = await fetch(`${this.baseUrl}{{ url }}`, {
+            method: '{{ endpoint.method | upper }}',
+            headers: { 'Content-Type': 'application/json', ...this.headers },
+            {%- if endpoint.request %}
+            body: JSON.stringify(request),
+            {%- endif %}
+        });
+        if (!response.ok) {
+            throw new Error(`{{ endpoint.method | upper }} {{ endpoint.path }} failed with ${response.status}`);
+        }
+        {%- if endpoint.response %}
+        return (await response.json()) as {{ endpoint.response.mapped_type }};
+        {%- endif %}
+    }
+{% endfor -%}
+}
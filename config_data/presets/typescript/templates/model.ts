@@ -0,0 +1,20 @@
+{# An interface for each model, in a file named with `modelFileName` #}
+{%- for import in imports -%}
+{% if import is ending_with(".ts") -%}
+{% set name = import | replace(from=".ts", to="") -%}
+import type { {{ name }} } from './{{ name }}';
+{% else -%}
+import {{ import }};
+{% endif -%}
+{% endfor -%}
+{% if imports %}
+{% endif -%}
+{% if description %}/** {{ description }} */
+{% endif -%}
+export interface {{ object_name }} {
+{%- for field in properties %}
+    {% if field.description %}/** {{ field.description }} */
+    {% endif -%}
+    {% if field.wire_name is matching("^[A-Za-z_$][A-Za-z0-9_$]*$") %}{{ field.wire_name }}{% else %}'{{ field.wire_name }}'{% endif %}{% if not field.required %}?{% endif %}: {{ field.mapped_type }};
+{%- endfor %}
+}
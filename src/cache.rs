@@ -0,0 +1,270 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use simplelog::{debug, warn};
+
+/// Directory the cache is stored in, inside the output folder
+pub const CACHE_DIR: &str = ".api-gen-cache";
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Hashes of merged OpenAPI documents that passed validation
+    validated: HashSet<String>,
+    /// Output file path to the hash of the inputs it was rendered from
+    outputs: HashMap<String, String>,
+}
+
+/// Content-hash cache used to skip validation and rendering when the inputs have not changed
+pub struct Cache {
+    dir: Option<PathBuf>,
+    /// Output files are keyed relative to this folder, so the cache is the same from any directory
+    root: PathBuf,
+    previous: Manifest,
+    current: Manifest,
+}
+
+impl Cache {
+    /// Loads the cache of the output folder, starting empty if it does not exist or cannot be read
+    pub fn load(output: &Path) -> Self {
+        let dir = output.join(CACHE_DIR);
+        let manifest_file = dir.join(MANIFEST_FILE_NAME);
+        let previous = match std::fs::read_to_string(&manifest_file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring unreadable cache manifest {:?}: {}",
+                    manifest_file, e
+                );
+                Manifest::default()
+            }),
+            Err(_) => Manifest::default(),
+        };
+        Self {
+            dir: Some(dir),
+            root: output.to_path_buf(),
+            previous,
+            current: Manifest::default(),
+        }
+    }
+
+    /// A cache that never has a hit and is never saved
+    pub fn disabled() -> Self {
+        Self {
+            dir: None,
+            root: PathBuf::new(),
+            previous: Manifest::default(),
+            current: Manifest::default(),
+        }
+    }
+
+    pub fn is_validated(&self, spec_hash: &str) -> bool {
+        self.previous.validated.contains(spec_hash)
+    }
+
+    pub fn mark_validated(&mut self, spec_hash: String) {
+        self.current.validated.insert(spec_hash);
+    }
+
+    /// Checks if the output file exists and was rendered from the same inputs.
+    /// Files that were already written during this run are never fresh, as they may have been overwritten.
+    pub fn is_fresh(&self, output: &Path, inputs_hash: &str) -> bool {
+        let key = self.key(output);
+        output.exists()
+            && !self.current.outputs.contains_key(&key)
+            && self
+                .previous
+                .outputs
+                .get(&key)
+                .is_some_and(|hash| hash == inputs_hash)
+    }
+
+    pub fn record(&mut self, output: &Path, inputs_hash: String) {
+        let key = self.key(output);
+        self.current.outputs.insert(key, inputs_hash);
+    }
+
+    fn key(&self, output: &Path) -> String {
+        output
+            .strip_prefix(&self.root)
+            .unwrap_or(output)
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+
+    /// Writes the entries recorded during this run, dropping the ones that are no longer used
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(dir) = &self.dir {
+            std::fs::create_dir_all(dir)?;
+            let manifest_file = dir.join(MANIFEST_FILE_NAME);
+            debug!("Saving cache manifest to {:?}", manifest_file);
+            std::fs::write(manifest_file, serde_json::to_string(&self.current)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hashes the parts in order, returning a hex encoded SHA-256
+pub fn hash(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Hashes a serializable value with its map keys sorted so the hash is stable between runs
+pub fn hash_value<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    let value = serde_json::to_value(value)?;
+    let mut canonical = String::new();
+    write_canonical(&value, &mut canonical);
+    Ok(hash(&[canonical.as_bytes()]))
+}
+
+fn write_canonical(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for key in keys {
+                out.push_str(&serde_json::Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+                out.push(',');
+            }
+            out.push('}');
+        }
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for item in items {
+                write_canonical(item, out);
+                out.push(',');
+            }
+            out.push(']');
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty output folder unique to the test
+    fn output_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("api-gen-cache-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Renders the file and saves the cache like a generate run
+    fn run(output: &Path, file: &Path, inputs_hash: &str) -> bool {
+        let mut cache = Cache::load(output);
+        let fresh = cache.is_fresh(file, inputs_hash);
+        if !fresh {
+            std::fs::write(file, inputs_hash).unwrap();
+        }
+        cache.record(file, inputs_hash.to_string());
+        cache.save().unwrap();
+        fresh
+    }
+
+    #[test]
+    fn file_is_fresh_when_inputs_are_unchanged() {
+        let output = output_dir("fresh");
+        let file = output.join("model.dart");
+        assert!(!run(&output, &file, "a"));
+        assert!(run(&output, &file, "a"));
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn changed_inputs_invalidate_the_file() {
+        let output = output_dir("changed");
+        let file = output.join("model.dart");
+        run(&output, &file, "a");
+        assert!(!run(&output, &file, "b"));
+        assert!(!run(&output, &file, "a"));
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn deleted_file_is_not_fresh() {
+        let output = output_dir("deleted");
+        let file = output.join("model.dart");
+        run(&output, &file, "a");
+        std::fs::remove_file(&file).unwrap();
+        assert!(!run(&output, &file, "a"));
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn file_written_twice_in_a_run_is_not_fresh() {
+        let output = output_dir("twice");
+        let file = output.join("model.dart");
+        run(&output, &file, "a");
+        let mut cache = Cache::load(&output);
+        assert!(cache.is_fresh(&file, "a"));
+        cache.record(&file, "a".to_string());
+        assert!(!cache.is_fresh(&file, "a"));
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn entries_not_recorded_are_dropped() {
+        let output = output_dir("dropped");
+        let first = output.join("first.dart");
+        let second = output.join("second.dart");
+        run(&output, &first, "a");
+        run(&output, &second, "a");
+        let cache = Cache::load(&output);
+        assert!(!cache.is_fresh(&first, "a"));
+        assert!(cache.is_fresh(&second, "a"));
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn files_are_keyed_relative_to_the_output_folder() {
+        let output = output_dir("relative");
+        let file = output.join("model.dart");
+        run(&output, &file, "a");
+        let manifest = std::fs::read_to_string(output.join(CACHE_DIR).join(MANIFEST_FILE_NAME));
+        let manifest: Manifest = serde_json::from_str(&manifest.unwrap()).unwrap();
+        assert_eq!(manifest.outputs.get("model.dart"), Some(&"a".to_string()));
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn disabled_cache_is_never_fresh_or_saved() {
+        let output = output_dir("disabled");
+        let file = output.join("model.dart");
+        std::fs::write(&file, "").unwrap();
+        let mut cache = Cache::disabled();
+        cache.mark_validated("spec".to_string());
+        cache.record(&file, "a".to_string());
+        cache.save().unwrap();
+        assert!(!cache.is_validated("spec"));
+        assert!(!cache.is_fresh(&file, "a"));
+        assert!(!output.join(CACHE_DIR).exists());
+        std::fs::remove_dir_all(output).unwrap();
+    }
+
+    #[test]
+    fn hash_value_ignores_map_key_order() {
+        let a: HashMap<&str, u32> = [("a", 1), ("b", 2)].into_iter().collect();
+        let b: HashMap<&str, u32> = [("b", 2), ("a", 1)].into_iter().collect();
+        assert_eq!(hash_value(&a).unwrap(), hash_value(&b).unwrap());
+        assert_ne!(hash(&[b"ab", b"c"]), hash(&[b"a", b"bc"]));
+    }
+}
@@ -1,81 +1,1351 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use anyhow::Ok;
+use jsonschema::{
+    error::{TypeKind, ValidationErrorKind},
+    paths::{JSONPointer, PathChunk},
+    primitive_type::PrimitiveType,
+    JSONSchema, ValidationError,
+};
+use regex::Regex;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use simplelog::{debug, warn};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::{
+    lint::{self, RuleLevel},
+    source_location::{locate_toml, locate_yaml},
+    template_pack::{self, PackRef},
+};
+
+/// Prefix of the environment variables that override config values
+pub const ENV_PREFIX: &str = "API_GEN_";
+
+/// Configuration for api-gen, mapping OpenAPI types to the types of the target language
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[schemars(title = "api-gen config")]
 pub struct Config {
+    /// OpenAPI type (`String`, `Number`, `Integer` or `Boolean`) to the target language type
+    #[schemars(schema_with = "types_schema")]
     pub types: HashMap<String, Type>,
+    /// Extra values made available to the templates, e.g. `feature: Orders` or `nullSafety: true`
     #[serde(default)]
-    pub extended: HashMap<String, String>,
+    pub extended: HashMap<String, serde_json::Value>,
+    /// Layout of an array type, where `{type}` is replaced with the item type (e.g. `List<{type}>`)
     #[serde(rename = "arrayLayout")]
+    #[schemars(length(min = 1), regex(path = "LAYOUT_PATTERN"))]
     pub array_layout: String,
+    /// Layout of the type of a property that is not required, where `{type}` is replaced with the
+    /// type (e.g. `Option<{type}>`)
+    #[serde(
+        default,
+        rename = "optionalLayout",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(regex(path = "LAYOUT_PATTERN"))]
+    pub optional_layout: Option<String>,
+    /// Layout of the type of a required property that is nullable, where `{type}` is replaced
+    /// with the type (e.g. `{type}?`)
+    #[serde(
+        default,
+        rename = "nullableLayout",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(regex(path = "LAYOUT_PATTERN"))]
+    pub nullable_layout: Option<String>,
+    /// Tera template for the file name of each model, used by `model.` and `model-endpoint.`
+    /// templates
     #[serde(default, rename = "modelFileName")]
     pub model_file_name: Option<String>,
+    /// Config file this one is merged on top of, relative to this file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<PathBuf>,
+    /// Installed template pack whose templates and partial config are used beneath this config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pack: Option<PackRef>,
+    /// Folder containing the templates, relative to the config file. Defaults to `templates`, or
+    /// the templates of the `pack`
+    #[serde(default = "default_templates")]
+    pub templates: PathBuf,
+    /// Folder the files are generated in, relative to the config file. `--output` takes
+    /// precedence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    /// Types used for specific properties instead of the `types` mapping, the first match is used
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<TypeOverride>,
+    /// Vendor extension on a schema that pins its type and takes precedence over `overrides` and
+    /// `types`, e.g. `x-dart-type`. `{target}` is replaced with the `target`, which defaults it
+    /// to `x-{target}-type`
+    #[serde(
+        default,
+        rename = "typeExtension",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(regex(path = "TYPE_EXTENSION_PATTERN"))]
+    pub type_extension: Option<String>,
+    /// Name of the target language in vendor extension keys, e.g. `dart` for `x-dart-type`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(regex(path = "TARGET_PATTERN"))]
+    pub target: Option<String>,
+    /// How names are turned into identifiers by the `identifier`, `field_name` and `type_name` filters
+    #[serde(default)]
+    pub naming: Naming,
+    /// Severity of the lint rules run by `validate`, by rule ID, e.g. `missing-description: error`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[schemars(schema_with = "lint::config_schema")]
+    pub lint: HashMap<String, RuleLevel>,
+}
+
+const LAYOUT_PATTERN: &str = r"\{type\}";
+const TYPE_EXTENSION_PATTERN: &str = "^x-";
+const TARGET_PATTERN: &str = "^[a-z][a-z0-9]*$";
+
+/// What a value failing each pattern of the schema must look like instead
+const PATTERN_MESSAGES: [(&str, &str); 3] = [
+    (LAYOUT_PATTERN, "must contain the '{type}' placeholder"),
+    (TYPE_EXTENSION_PATTERN, "must start with 'x-'"),
+    (
+        TARGET_PATTERN,
+        "must be a lowercase name such as 'dart' or 'ts'",
+    ),
+];
+
+/// Schema of `types`, which needs at least one type
+fn types_schema(gen: &mut SchemaGenerator) -> Schema {
+    let mut schema = gen.subschema_for::<HashMap<String, Type>>().into_object();
+    schema.object().min_properties = Some(1);
+    schema.into()
+}
+
+fn default_templates() -> PathBuf {
+    PathBuf::from("templates")
 }
 
 impl Config {
+    /// Validates the config against the JSON Schema returned by [`schema`]
     pub fn validate(&self) -> anyhow::Result<()> {
-        if self.types.is_empty() {
-            return Err(anyhow::anyhow!("Types cannot be empty"));
-        }
-        if self.array_layout.is_empty() {
-            return Err(anyhow::anyhow!("Array layout cannot be empty"));
-        }
-        if !self.array_layout.contains("{type}") {
+        check_schema(&serde_json::to_value(self)?, |_| None)?;
+        TypeOverrides::new(&self.overrides)?;
+        if self.target.is_none()
+            && self
+                .type_extension
+                .as_ref()
+                .is_some_and(|x| x.contains("{target}"))
+        {
             return Err(anyhow::anyhow!(
-                "Array layout must contain '{{type}}' placeholder"
+                "Invalid config: typeExtension uses '{{target}}' but no target is set"
             ));
         }
         Ok(())
     }
+
+    /// The vendor extension that pins the type of a schema, with `{target}` replaced. Defaults to
+    /// `x-{target}-type` when a target is set
+    pub fn type_extension_key(&self) -> Option<String> {
+        match (&self.type_extension, &self.target) {
+            (Some(extension), Some(target)) => Some(extension.replace("{target}", target)),
+            (Some(extension), None) => Some(extension.clone()),
+            (None, Some(target)) => Some(format!("x-{}-type", target)),
+            (None, None) => None,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Target language type for an OpenAPI type
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct Type {
+    /// Type used when no format matches
     pub default: String,
-    pub format: Option<HashMap<String, String>>,
+    /// Format (e.g. `Int64`, `DateTime` or a custom format as it is in the spec) to the target
+    /// language type
+    pub format: Option<HashMap<String, TargetType>>,
+    /// Import needed by a model using the `default` type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import: Option<String>,
 }
 
-pub fn parse_config_file(path: Option<PathBuf>) -> anyhow::Result<Config> {
-    if let Some(file) = path {
-        if file.exists() && file.is_file() {
-            let config_file = std::fs::read_to_string(&file).unwrap();
-            let extension = file.extension().unwrap();
-            let config: Config = if extension == "json" {
-                serde_json::from_str(&config_file).unwrap()
-            } else {
-                serde_yaml::from_str(&config_file).unwrap()
+/// A target language type of a format or pinned by a vendor extension, on its own or with the
+/// import it needs, e.g. `DateTime` or `{ type: Decimal, import: package:decimal/decimal.dart }`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum TargetType {
+    Name(String),
+    WithImport {
+        /// Target language type, e.g. `Decimal`
+        #[serde(rename = "type")]
+        target: String,
+        /// Import needed by a model using this type, e.g. `package:decimal/decimal.dart`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        import: Option<String>,
+    },
+}
+
+impl TargetType {
+    pub fn target(&self) -> &str {
+        match self {
+            TargetType::Name(target) | TargetType::WithImport { target, .. } => target,
+        }
+    }
+
+    pub fn import(&self) -> Option<&str> {
+        match self {
+            TargetType::Name(_) => None,
+            TargetType::WithImport { import, .. } => import.as_deref(),
+        }
+    }
+}
+
+impl Display for TargetType {
+    /// Writes the type as a YAML flow value
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetType::Name(target) => write!(f, "{}", yaml_scalar(target)),
+            TargetType::WithImport {
+                target,
+                import: Some(import),
+            } => write!(
+                f,
+                "{{ type: {}, import: {} }}",
+                yaml_scalar(target),
+                yaml_scalar(import)
+            ),
+            TargetType::WithImport {
+                target,
+                import: None,
+            } => write!(f, "{{ type: {} }}", yaml_scalar(target)),
+        }
+    }
+}
+
+/// Quotes the value unless it reads back as the same string as a plain scalar in a YAML
+/// flow mapping, e.g. `"#string"` or `"chrono::{DateTime, Utc}"`
+pub fn yaml_scalar(value: &str) -> String {
+    let plain = !value.contains([',', '[', ']', '{', '}', '#'])
+        && serde_yaml::from_str::<serde_yaml::Value>(value)
+            .is_ok_and(|x| x == serde_yaml::Value::String(value.to_string()));
+    if plain {
+        value.to_string()
+    } else {
+        serde_json::Value::String(value.to_string()).to_string()
+    }
+}
+
+/// How names are turned into identifiers by the `identifier`, `field_name` and `type_name` filters
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Naming {
+    /// Identifiers that are escaped with the `escape` strategy, e.g. `class`
+    #[serde(default, rename = "reservedWords")]
+    pub reserved_words: Vec<String>,
+    /// How reserved words are escaped. Defaults to `suffix`
+    #[serde(default)]
+    pub escape: Escape,
+    /// Text added by the `prefix` and `suffix` strategies and before identifiers starting with a digit
+    #[serde(default = "default_escape_with", rename = "escapeWith")]
+    #[schemars(length(min = 1))]
+    pub escape_with: String,
+    /// Case of the names from the `field_name` filter
+    #[serde(default, rename = "fieldCase", skip_serializing_if = "Option::is_none")]
+    pub field_case: Option<Case>,
+    /// Case of the names from the `type_name` filter
+    #[serde(default, rename = "typeCase", skip_serializing_if = "Option::is_none")]
+    pub type_case: Option<Case>,
+}
+
+impl Default for Naming {
+    fn default() -> Self {
+        Self {
+            reserved_words: Vec::new(),
+            escape: Escape::default(),
+            escape_with: default_escape_with(),
+            field_case: None,
+            type_case: None,
+        }
+    }
+}
+
+fn default_escape_with() -> String {
+    "_".to_string()
+}
+
+/// How a reserved word is escaped
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Escape {
+    /// Adds `escapeWith` before the word
+    Prefix,
+    /// Adds `escapeWith` after the word
+    #[default]
+    Suffix,
+    /// Raw identifier, e.g. `r#type`
+    Raw,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Case {
+    Camel,
+    Pascal,
+    Snake,
+    ScreamingSnake,
+}
+
+/// Type used for the properties matching `property` or `pattern`, optionally only in `model`
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TypeOverride {
+    /// Name of the property, e.g. `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub property: Option<String>,
+    /// Regex the property name must match, e.g. `.*At$`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Name of the model the property must be in, e.g. `Order`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Target language type to use
+    #[serde(rename = "type")]
+    pub target: String,
+    /// Import needed by a model using this type, e.g. `package:decimal/decimal.dart`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import: Option<String>,
+    /// Value used by `json_value` and `json_min_value`, where strings such as `@Guid` are mocked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample: Option<serde_json::Value>,
+}
+
+/// The `overrides` from the config with their patterns compiled
+pub struct TypeOverrides {
+    rules: Vec<(TypeOverride, Option<Regex>)>,
+}
+
+impl TypeOverrides {
+    pub fn new(overrides: &[TypeOverride]) -> anyhow::Result<Self> {
+        let rules = overrides
+            .iter()
+            .map(|rule| {
+                if rule.property.is_none() && rule.pattern.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Invalid override with type '{}', it must set 'property' or 'pattern'",
+                        rule.target
+                    ));
+                }
+                let pattern = match &rule.pattern {
+                    Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                        anyhow::anyhow!("Invalid override pattern '{}': {}", pattern, e)
+                    })?),
+                    None => None,
+                };
+                Ok((rule.clone(), pattern))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Finds the type for the property, where `models` are the names the model it is in is known by
+    pub fn find(&self, property: &str, models: &[&str]) -> Option<&str> {
+        self.find_rule(property, models)
+            .map(|rule| rule.target.as_str())
+    }
+
+    /// Finds the first rule matching the property
+    pub fn find_rule(&self, property: &str, models: &[&str]) -> Option<&TypeOverride> {
+        self.rules
+            .iter()
+            .find(|(rule, pattern)| {
+                rule.property.as_ref().is_none_or(|x| x == property)
+                    && pattern.as_ref().is_none_or(|x| x.is_match(property))
+                    && rule
+                        .model
+                        .as_ref()
+                        .is_none_or(|x| models.contains(&x.as_str()))
+            })
+            .map(|(rule, _)| rule)
+    }
+}
+
+/// JSON Schema describing the config file, used by `config schema` and [`Config::validate`]
+pub fn schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(Config)).expect("the config schema is JSON")
+}
+
+/// An error found by validating a value against the config schema
+struct SchemaError {
+    /// Keys and item indexes leading to the value or unknown key the error is about
+    keys: Vec<String>,
+    message: String,
+}
+
+/// Validates the value against the config schema, prefixing each error with where it is when known
+fn check_schema(
+    value: &serde_json::Value,
+    locate: impl Fn(&[String]) -> Option<String>,
+) -> anyhow::Result<()> {
+    let schema = schema();
+    let validator = JSONSchema::compile(&schema)
+        .map_err(|e| anyhow::anyhow!("Invalid config schema: {}", e))?;
+    let errors: Vec<String> = match validator.validate(value) {
+        Result::Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .flat_map(|error| schema_errors(&schema, &error))
+            .map(|error| match locate(&error.keys) {
+                Some(location) => format!("{}: {}", location, error.message),
+                None => error.message,
+            })
+            .collect(),
+    };
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Invalid config:\n  {}",
+            errors.join("\n  ")
+        ))
+    }
+}
+
+/// Describes the validation error with the config keys, e.g. `config.types.String`, one error for
+/// each unknown key
+fn schema_errors(schema: &serde_json::Value, error: &ValidationError) -> Vec<SchemaError> {
+    let keys = pointer_keys(&error.instance_path);
+    let path =
+        error
+            .instance_path
+            .iter()
+            .zip(&keys)
+            .fold("config".to_string(), |path, (chunk, key)| match chunk {
+                PathChunk::Index(_) => format!("{}[{}]", path, key),
+                _ => format!("{}.{}", path, key),
+            });
+    let message = match &error.kind {
+        ValidationErrorKind::AdditionalProperties { unexpected } => {
+            let known = known_keys(schema, &error.schema_path);
+            return unexpected
+                .iter()
+                .map(|key| {
+                    let message = match suggest(key, &known) {
+                        Some(suggestion) => format!(
+                            "{} has an unknown key '{}', did you mean '{}'?",
+                            path, key, suggestion
+                        ),
+                        None => format!("{} has an unknown key '{}'", path, key),
+                    };
+                    let mut keys = keys.clone();
+                    keys.push(key.clone());
+                    SchemaError { keys, message }
+                })
+                .collect();
+        }
+        ValidationErrorKind::Type { kind } => {
+            let types: Vec<String> = match kind {
+                TypeKind::Single(single) => vec![single.to_string()],
+                TypeKind::Multiple(types) => types
+                    .into_iter()
+                    .map(|x: PrimitiveType| x.to_string())
+                    .collect(),
             };
-            Ok(config)
-        } else {
-            Err(anyhow::anyhow!(
+            format!("{} must be of type {}", path, types.join(" or "))
+        }
+        ValidationErrorKind::Required { property } => format!(
+            "{} is missing the required key '{}'",
+            path,
+            property.as_str().unwrap_or_default()
+        ),
+        ValidationErrorKind::MinProperties { .. } | ValidationErrorKind::MinLength { .. } => {
+            format!("{} cannot be empty", path)
+        }
+        ValidationErrorKind::Pattern { pattern } => {
+            match PATTERN_MESSAGES.iter().find(|(x, _)| x == pattern) {
+                Some((_, message)) => format!("{}: {}", path, message),
+                None => format!("{} must match the pattern '{}'", path, pattern),
+            }
+        }
+        ValidationErrorKind::Enum { options } => format!("{} must be one of {}", path, options),
+        ValidationErrorKind::AnyOf | ValidationErrorKind::OneOfNotValid => {
+            format!("{} does not match any of the allowed schemas", path)
+        }
+        _ => format!("{}: {}", path, error),
+    };
+    vec![SchemaError { keys, message }]
+}
+
+fn pointer_keys(pointer: &JSONPointer) -> Vec<String> {
+    pointer
+        .iter()
+        .map(|chunk| match chunk {
+            PathChunk::Property(key) => key.to_string(),
+            PathChunk::Index(index) => index.to_string(),
+            PathChunk::Keyword(keyword) => keyword.to_string(),
+        })
+        .collect()
+}
+
+/// The properties of the schema whose `additionalProperties` keyword is at the path. The path
+/// leaves out the `$ref`s it went through, so they are followed when a key is not found
+fn known_keys<'a>(schema: &'a serde_json::Value, path: &JSONPointer) -> Vec<&'a String> {
+    let resolve = |node: &'a serde_json::Value| {
+        node.get("$ref")
+            .and_then(serde_json::Value::as_str)
+            .and_then(|reference| schema.pointer(reference.strip_prefix('#')?))
+            .unwrap_or(node)
+    };
+    let mut keys = pointer_keys(path);
+    keys.pop();
+    let mut node = Some(schema);
+    for key in &keys {
+        node = node.and_then(|current| {
+            let child = |x: &'a serde_json::Value| match x {
+                serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+                _ => x.get(key),
+            };
+            child(current).or_else(|| child(resolve(current)))
+        });
+    }
+    node.and_then(|x| resolve(x).get("properties"))
+        .and_then(serde_json::Value::as_object)
+        .map(|properties| properties.keys().collect())
+        .unwrap_or_default()
+}
+
+/// Finds the known key closest to an unknown one
+fn suggest<'a>(key: &str, known: &[&'a String]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|x| (x, strsim::jaro_winkler(key, x)))
+        .filter(|(_, score)| *score > 0.8)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(x, _)| x.as_str())
+}
+
+/// Where a config value came from
+#[derive(Debug, Clone)]
+pub enum Source {
+    File(PathBuf),
+    Env(String),
+    Cli(String),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Env(name) => write!(f, "env {}", name),
+            Source::Cli(arg) => write!(f, "--set {}", arg),
+        }
+    }
+}
+
+/// Config merged from its files, environment variables and `--set` overrides,
+/// keeping track of where each value came from
+pub struct LayeredConfig {
+    /// The config file the other layers are applied on top of
+    file: PathBuf,
+    value: Value,
+    sources: HashMap<Vec<String>, Source>,
+}
+
+impl LayeredConfig {
+    /// Loads the config file, or the nearest one to the current directory or the OpenAPI file(s),
+    /// and the files it extends, then applies the `API_GEN_*` environment variables and the
+    /// `--set key=value` overrides, in increasing order of precedence
+    pub fn load(
+        path: Option<PathBuf>,
+        api: Option<&Path>,
+        overrides: &[String],
+    ) -> anyhow::Result<Self> {
+        Self::load_with_env(path, api, std::env::vars(), overrides)
+    }
+
+    /// Loads the config as [`LayeredConfig::load`] does, with the `API_GEN_*` variables taken
+    /// from `env` instead of the environment of the process
+    fn load_with_env(
+        path: Option<PathBuf>,
+        api: Option<&Path>,
+        env: impl IntoIterator<Item = (String, String)>,
+        overrides: &[String],
+    ) -> anyhow::Result<Self> {
+        let file = match path {
+            Some(file) => file,
+            None => find_config_file(api)?,
+        };
+        let mut layered = Self {
+            file: file.clone(),
+            value: Value::Mapping(Mapping::new()),
+            sources: HashMap::new(),
+        };
+        layered.merge_file(&file, &mut Vec::new())?;
+
+        let mut vars: Vec<(String, String)> = env
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+            .collect();
+        vars.sort();
+        for (name, value) in vars {
+            match env_key_path(&name[ENV_PREFIX.len()..]) {
+                Some(path) => layered.set(&path, &value, Source::Env(name)),
+                None => warn!(
+                    "Ignoring environment variable {}, it does not match a config key",
+                    name
+                ),
+            }
+        }
+
+        for arg in overrides {
+            let (key, value) = arg
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid --set '{}', expected KEY=VALUE", arg))?;
+            let path: Vec<String> = key.split('.').map(String::from).collect();
+            if path.iter().any(|x| x.is_empty()) {
+                return Err(anyhow::anyhow!("Invalid --set key '{}'", key));
+            }
+            if path[0] == "extends" || path[0] == "pack" {
+                return Err(anyhow::anyhow!(
+                    "'{}' can only be set in a config file",
+                    path[0]
+                ));
+            }
+            layered.set(&path, value, Source::Cli(arg.clone()));
+        }
+        Ok(layered)
+    }
+
+    /// Validates the merged values against the config schema and deserializes them
+    pub fn config(&self) -> anyhow::Result<Config> {
+        check_schema(&serde_json::to_value(&self.value)?, |keys| {
+            self.locate(keys)
+        })?;
+        let mut config: Config = serde_yaml::from_value(self.value.clone())?;
+        config.templates = self.resolve_path("templates", &config.templates);
+        config.output = config
+            .output
+            .as_ref()
+            .map(|output| self.resolve_path("output", output));
+        Ok(config)
+    }
+
+    /// Where the value or key at the path was set, as `file:line:column` when it was set in a file
+    fn locate(&self, keys: &[String]) -> Option<String> {
+        let source = self
+            .sources
+            .iter()
+            .filter(|(path, _)| path.starts_with(keys) || keys.starts_with(path))
+            .min_by(|a, b| a.0.cmp(b.0))
+            .map(|(_, source)| source)?;
+        let Source::File(file) = source else {
+            return Some(source.to_string());
+        };
+        let position = std::fs::read_to_string(file).ok().and_then(|content| {
+            if is_cargo_manifest(file) {
+                let mut path = vec!["package", "metadata", CARGO_METADATA_KEY];
+                path.extend(keys.iter().map(String::as_str));
+                locate_toml(&content, &path)
+            } else if file.extension().is_some_and(|x| x == "toml") {
+                locate_toml(&content, keys)
+            } else {
+                locate_yaml(&content, keys)
+            }
+        });
+        Some(match position {
+            Some(position) => format!("{}:{}:{}", file.display(), position.line, position.column),
+            None => file.display().to_string(),
+        })
+    }
+
+    /// Resolves a relative path against the directory of the config file it was set in.
+    /// Paths set by environment variables or `--set` stay relative to the current directory
+    fn resolve_path(&self, key: &str, path: &Path) -> PathBuf {
+        let dir = match self.sources.get([key.to_string()].as_slice()) {
+            Some(Source::File(file)) => file.parent(),
+            Some(_) => None,
+            None => self.file.parent(),
+        };
+        match dir {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+
+    /// The merged config as YAML, with the source of each value as a comment
+    pub fn show(&self) -> anyhow::Result<String> {
+        let mut out = String::new();
+        self.write(&self.value, &mut Vec::new(), &mut out)?;
+        Ok(out)
+    }
+
+    fn write(&self, value: &Value, path: &mut Vec<String>, out: &mut String) -> anyhow::Result<()> {
+        if let Value::Mapping(map) = value {
+            let indent = "  ".repeat(path.len());
+            for (key, item) in map {
+                path.push(key_string(key));
+                match item {
+                    Value::Mapping(children) if !children.is_empty() => {
+                        out.push_str(&format!("{}{}:\n", indent, inline_yaml(key)?));
+                        self.write(item, path, out)?;
+                    }
+                    _ => {
+                        out.push_str(&format!(
+                            "{}{}: {}",
+                            indent,
+                            inline_yaml(key)?,
+                            inline_yaml(item)?
+                        ));
+                        if let Some(source) = self.sources.get(path.as_slice()) {
+                            out.push_str(&format!("  # {}", source));
+                        }
+                        out.push('\n');
+                    }
+                }
+                path.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges the file on top of the current values, after the file it extends and the pack it uses
+    fn merge_file(&mut self, file: &Path, chain: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+        if !file.is_file() {
+            return Err(anyhow::anyhow!(
                 "Config file '{}' is not a file",
                 file.display()
-            ))
+            ));
         }
-    } else {
-        let cwd = std::env::current_dir().unwrap();
-        let json_config_file = cwd.join("config.json");
-        let yml_config_file = cwd.join("config.yml");
-        let yaml_config_file = cwd.join("config.yaml");
-        if json_config_file.exists() && json_config_file.is_file() {
-            let config_file = std::fs::read_to_string(json_config_file).unwrap();
-            let config: Config = serde_json::from_str(&config_file).unwrap();
-            Ok(config)
-        } else if yml_config_file.exists() && yml_config_file.is_file() {
-            let config_file = std::fs::read_to_string(yml_config_file).unwrap();
-            let config: Config = serde_yaml::from_str(&config_file).unwrap();
-            Ok(config)
-        } else if yaml_config_file.exists() && yaml_config_file.is_file() {
-            let config_file = std::fs::read_to_string(yaml_config_file).unwrap();
-            let config: Config = serde_yaml::from_str(&config_file).unwrap();
-            Ok(config)
+        let is_root = chain.is_empty();
+        let canonical = file.canonicalize()?;
+        if chain.contains(&canonical) {
+            return Err(anyhow::anyhow!(
+                "Config file '{}' extends itself",
+                file.display()
+            ));
+        }
+        chain.push(canonical);
+
+        debug!("Loading config file {:?}", file);
+        let mut value = read_config_value(file)?;
+        let base = match value.get("extends") {
+            Some(Value::String(base)) => Some(base.clone()),
+            Some(_) => {
+                return Err(anyhow::anyhow!(
+                    "'extends' in config file '{}' must be a path",
+                    file.display()
+                ))
+            }
+            None => None,
+        };
+        if let Some(base) = base {
+            let dir = file.parent().unwrap_or(Path::new(""));
+            self.merge_file(&dir.join(base), chain)?;
+            if !is_root {
+                if let Value::Mapping(map) = &mut value {
+                    map.remove("extends");
+                }
+            }
+        }
+        if let Some(pack) = value.get("pack") {
+            let pack: PackRef = serde_yaml::from_value(pack.clone()).map_err(|e| {
+                anyhow::anyhow!("Invalid 'pack' in config file '{}': {}", file.display(), e)
+            })?;
+            self.merge_pack(&pack)?;
+        }
+        self.merge(value, Source::File(file.to_path_buf()));
+        Ok(())
+    }
+
+    /// Merges the partial config of the pack, with `templates` defaulting to the pack's templates
+    fn merge_pack(&mut self, pack: &PackRef) -> anyhow::Result<()> {
+        let dir = pack.installed_path()?;
+        let file = dir.join(template_pack::CONFIG_FILE_NAME);
+        let mut value = if file.is_file() {
+            read_config_value(&file)?
         } else {
-            Err(anyhow::anyhow!(
-                "Config file not found in {}",
-                cwd.display()
-            ))
+            Value::Mapping(Mapping::new())
+        };
+        if let Value::Mapping(map) = &mut value {
+            for key in ["extends", "pack"] {
+                if map.contains_key(key) {
+                    return Err(anyhow::anyhow!(
+                        "'{}' cannot be set in the config of template pack {}@{}",
+                        key,
+                        pack.name,
+                        pack.version
+                    ));
+                }
+            }
+            if !map.contains_key("templates") {
+                map.insert(
+                    Value::String("templates".to_string()),
+                    Value::String(template_pack::TEMPLATES_FOLDER_NAME.to_string()),
+                );
+            }
+        }
+        debug!(
+            "Using template pack {}@{} from {:?}",
+            pack.name, pack.version, dir
+        );
+        self.merge(value, Source::File(file));
+        Ok(())
+    }
+
+    /// Sets the value at the path, read as a YAML scalar so `true` and `8` are the same as
+    /// in a config file
+    fn set(&mut self, path: &[String], value: &str, source: Source) {
+        let mut layer = match serde_yaml::from_str::<Value>(value) {
+            Result::Ok(Value::Mapping(_) | Value::Sequence(_) | Value::Tagged(_)) | Err(_) => {
+                Value::String(value.to_string())
+            }
+            Result::Ok(scalar) => scalar,
+        };
+        for key in path.iter().rev() {
+            let mut map = Mapping::new();
+            map.insert(Value::String(key.clone()), layer);
+            layer = Value::Mapping(map);
+        }
+        self.merge(layer, source);
+    }
+
+    fn merge(&mut self, layer: Value, source: Source) {
+        record_sources(&layer, &mut Vec::new(), &source, &mut self.sources);
+        merge_values(&mut self.value, layer);
+    }
+}
+
+/// Deep merges the layer into the base, where mappings are merged key by key and any other value replaces the base
+fn merge_values(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Mapping(base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
         }
+        (base, layer) => *base = layer,
+    }
+}
+
+fn record_sources(
+    value: &Value,
+    path: &mut Vec<String>,
+    source: &Source,
+    sources: &mut HashMap<Vec<String>, Source>,
+) {
+    match value {
+        Value::Mapping(map) if !map.is_empty() => {
+            for (key, item) in map {
+                path.push(key_string(key));
+                record_sources(item, path, source, sources);
+                path.pop();
+            }
+        }
+        _ => {
+            sources.insert(path.clone(), source.clone());
+        }
+    }
+}
+
+fn key_string(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        _ => serde_yaml::to_string(key)
+            .map(|x| x.trim_end().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Serializes the value on a single line, using the JSON flow style for collections and multi-line strings
+fn inline_yaml(value: &Value) -> anyhow::Result<String> {
+    Ok(match value {
+        Value::Sequence(_) | Value::Mapping(_) => serde_json::to_string(value)?,
+        Value::String(text) if text.contains('\n') => serde_json::to_string(value)?,
+        _ => serde_yaml::to_string(value)?.trim_end().to_string(),
+    })
+}
+
+/// Maps an environment variable name without the prefix to a config key path,
+/// e.g. `ARRAY_LAYOUT` to `arrayLayout` and `EXTENDED__feature` to `extended.feature`
+fn env_key_path(name: &str) -> Option<Vec<String>> {
+    let mut segments = name.split("__");
+    let first = segments.next()?.replace('_', "").to_lowercase();
+    let schema = schema();
+    let key = schema["properties"]
+        .as_object()?
+        .keys()
+        .find(|key| key.to_lowercase() == first && *key != "extends" && *key != "pack")?
+        .clone();
+    let path: Vec<String> = std::iter::once(key)
+        .chain(segments.map(String::from))
+        .collect();
+    if path.iter().any(|x| x.is_empty()) {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+/// Key of the config section in a `Cargo.toml`
+const CARGO_METADATA_KEY: &str = "api-gen";
+
+fn read_config_value(file: &Path) -> anyhow::Result<Value> {
+    let content = std::fs::read_to_string(file).map_err(|e| {
+        anyhow::anyhow!("Config file '{}' could not be read: {}", file.display(), e)
+    })?;
+    let parse_error = |e: &dyn Display| {
+        anyhow::anyhow!("Failed to parse config file '{}': {}", file.display(), e)
+    };
+    let value: Value = if is_cargo_manifest(file) {
+        let manifest: Value = toml::from_str(&content).map_err(|e| parse_error(&e))?;
+        cargo_metadata(&manifest).cloned().ok_or_else(|| {
+            anyhow::anyhow!(
+                "'{}' has no [package.metadata.{}] section",
+                file.display(),
+                CARGO_METADATA_KEY
+            )
+        })?
+    } else {
+        match file.extension().and_then(|x| x.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|e| parse_error(&e))?,
+            Some("toml") => toml::from_str(&content).map_err(|e| parse_error(&e))?,
+            // Any other file is read as YAML, whatever its extension
+            _ => serde_yaml::from_str(&content).map_err(|e| parse_error(&e))?,
+        }
+    };
+    match value {
+        Value::Mapping(_) => Ok(value),
+        Value::Null => Ok(Value::Mapping(Mapping::new())),
+        _ => Err(anyhow::anyhow!(
+            "Config file '{}' must contain a mapping of config keys",
+            file.display()
+        )),
+    }
+}
+
+fn is_cargo_manifest(file: &Path) -> bool {
+    file.file_name().is_some_and(|x| x == "Cargo.toml")
+}
+
+/// The `[package.metadata.api-gen]` section of a `Cargo.toml`
+fn cargo_metadata(manifest: &Value) -> Option<&Value> {
+    manifest
+        .get("package")?
+        .get("metadata")?
+        .get(CARGO_METADATA_KEY)
+}
+
+/// Checks if the file is a config file, or a `Cargo.toml` with an api-gen section
+fn is_config_file(file: &Path) -> bool {
+    if !file.is_file() {
+        return false;
+    }
+    if !is_cargo_manifest(file) {
+        return true;
+    }
+    std::fs::read_to_string(file)
+        .ok()
+        .and_then(|content| toml::from_str::<Value>(&content).ok())
+        .is_some_and(|manifest| cargo_metadata(&manifest).is_some())
+}
+
+/// Config file names in the order they are looked for in each directory
+const CONFIG_FILE_NAMES: [&str; 9] = [
+    "api-gen.yaml",
+    "api-gen.yml",
+    "api-gen.json",
+    "api-gen.toml",
+    "config.json",
+    "config.yml",
+    "config.yaml",
+    "config.toml",
+    "Cargo.toml",
+];
+
+/// Looks for the nearest config file the way git finds `.git`, by walking up the parent
+/// directories of the OpenAPI file(s) and then, if none is found, of the current directory.
+/// The spec's own config comes first so a spec in a subproject is generated with its config
+pub(crate) fn find_config_file(api: Option<&Path>) -> anyhow::Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    let mut starts = Vec::new();
+    if let Some(api) = api.and_then(|api| api.canonicalize().ok()) {
+        if api.is_dir() {
+            starts.push(api);
+        } else if let Some(parent) = api.parent() {
+            starts.push(parent.to_path_buf());
+        }
+    }
+    starts.push(cwd.clone());
+    nearest_config_file(&starts).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Config file not found in {} or any of its parent directories",
+            starts
+                .iter()
+                .map(|x| x.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })
+}
+
+/// The first config file found walking up from each start in turn
+fn nearest_config_file(starts: &[PathBuf]) -> Option<PathBuf> {
+    starts
+        .iter()
+        .flat_map(|start| start.ancestors())
+        .flat_map(|dir| CONFIG_FILE_NAMES.iter().map(move |name| dir.join(name)))
+        .find(|file| is_config_file(file))
+}
+
+/// Loads the layered config, see [`LayeredConfig::load`]
+pub fn parse_config_file(
+    path: Option<PathBuf>,
+    api: Option<&Path>,
+    overrides: &[String],
+) -> anyhow::Result<Config> {
+    LayeredConfig::load(path, api, overrides)?.config()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty folder unique to the test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("api-gen-config-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A config with every field set
+    fn full_config() -> Config {
+        let string = Type {
+            default: "String".to_string(),
+            format: Some(HashMap::from([
+                (
+                    "DateTime".to_string(),
+                    TargetType::Name("DateTime".to_string()),
+                ),
+                (
+                    "decimal".to_string(),
+                    TargetType::WithImport {
+                        target: "Decimal".to_string(),
+                        import: Some("package:decimal/decimal.dart".to_string()),
+                    },
+                ),
+            ])),
+            import: Some("package:app/date.dart".to_string()),
+        };
+        Config {
+            types: HashMap::from([("string".to_string(), string)]),
+            extended: HashMap::from([("feature".to_string(), "on".into())]),
+            array_layout: "List<{type}>".to_string(),
+            optional_layout: Some("{type}?".to_string()),
+            nullable_layout: Some("{type}?".to_string()),
+            model_file_name: Some("{{ name | snake_case }}.dart".to_string()),
+            extends: Some(PathBuf::from("base.yaml")),
+            pack: Some(PackRef {
+                name: "dart".to_string(),
+                version: "1.0.0".to_string(),
+            }),
+            templates: PathBuf::from("templates"),
+            output: Some(PathBuf::from("lib")),
+            overrides: vec![TypeOverride {
+                property: Some("id".to_string()),
+                pattern: Some("Id$".to_string()),
+                model: Some("Order".to_string()),
+                target: "OrderId".to_string(),
+                import: Some("package:app/ids.dart".to_string()),
+                sample: Some(serde_json::json!("@Guid")),
+            }],
+            type_extension: Some("x-{target}-type".to_string()),
+            target: Some("dart".to_string()),
+            naming: Naming {
+                reserved_words: vec!["class".to_string()],
+                escape: Escape::Prefix,
+                escape_with: "$".to_string(),
+                field_case: Some(Case::Camel),
+                type_case: Some(Case::Pascal),
+            },
+            lint: HashMap::from([(lint::RULES[0].id.to_string(), RuleLevel::Error)]),
+        }
+    }
+
+    #[test]
+    fn a_full_config_matches_the_schema() {
+        full_config().validate().unwrap();
+    }
+
+    #[test]
+    fn the_type_extension_is_for_the_target() {
+        let mut config = full_config();
+        assert_eq!(config.type_extension_key().as_deref(), Some("x-dart-type"));
+        config.type_extension = None;
+        config.target = Some("ts".to_string());
+        assert_eq!(config.type_extension_key().as_deref(), Some("x-ts-type"));
+        config.type_extension = Some("x-{target}-class".to_string());
+        config.target = None;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn schema_errors_are_described_with_the_config_keys() {
+        let value = serde_json::json!({
+            "types": { "String": { "default": "String" } },
+            "arrayLayout": "List",
+            "overrides": [{ "property": "id", "type": 1 }],
+            "naming": { "escape": "quote" },
+            "target": "Dart"
+        });
+        let error = check_schema(&value, |_| None).unwrap_err().to_string();
+        for message in [
+            "config.arrayLayout: must contain the '{type}' placeholder",
+            "config.overrides[0].type must be of type string",
+            "config.naming.escape does not match any of the allowed schemas",
+            "config.target: must be a lowercase name such as 'dart' or 'ts'",
+        ] {
+            assert!(error.contains(message), "{} in {}", message, error);
+        }
+    }
+
+    #[test]
+    fn an_override_needs_a_property_or_a_pattern() {
+        let mut config = full_config();
+        config.overrides[0].property = None;
+        config.overrides[0].pattern = None;
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "Invalid override with type 'OrderId', it must set 'property' or 'pattern'"
+        );
+    }
+
+    fn load_error(file: &Path, overrides: &[String]) -> String {
+        LayeredConfig::load(Some(file.to_path_buf()), None, overrides)
+            .unwrap()
+            .config()
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_with_their_location() {
+        let dir = test_dir("unknown-keys");
+        let file = dir.join("config.yaml");
+        std::fs::write(
+            &file,
+            "types:\n  String:\n    default: String\n    formatt: {}\narrayLayout: List<{type}>\narraylayout: x\n",
+        )
+        .unwrap();
+        let error = load_error(
+            &file,
+            &[
+                "extended.feature=x".to_string(),
+                "modelFileNme=x".to_string(),
+            ],
+        );
+        assert!(error.contains(&format!(
+            "{}:6:1: config has an unknown key 'arraylayout', did you mean 'arrayLayout'?",
+            file.display()
+        )));
+        assert!(error.contains(&format!(
+            "{}:4:5: config.types.String has an unknown key 'formatt', did you mean 'format'?",
+            file.display()
+        )));
+        assert!(error.contains(
+            "--set modelFileNme=x: config has an unknown key 'modelFileNme', did you mean 'modelFileName'?"
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn json_and_extensionless_configs_are_located() {
+        let dir = test_dir("formats");
+        let json = dir.join("config.json");
+        std::fs::write(&json, "{\n  \"types\": {},\n  \"arrayLayout\": 1\n}\n").unwrap();
+        let error = load_error(&json, &[]);
+        assert!(error.contains(&format!(
+            "{}:3:3: config.arrayLayout must be of type string",
+            json.display()
+        )));
+
+        let extensionless = dir.join("config");
+        std::fs::write(
+            &extensionless,
+            "types:\n  String:\n    default: String\narrayLayout: List<{type}>\n",
+        )
+        .unwrap();
+        assert!(LayeredConfig::load(Some(extensionless), None, &[])
+            .unwrap()
+            .config()
+            .is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_keys_in_cargo_metadata_are_located() {
+        let dir = test_dir("cargo-metadata");
+        let file = dir.join("Cargo.toml");
+        std::fs::write(
+            &file,
+            "[package]\nname = \"x\"\n\n[package.metadata.api-gen]\narrayLayout = \"List<{type}>\"\n\n[package.metadata.api-gen.types.String]\ndefaul = \"String\"\n",
+        )
+        .unwrap();
+        let error = load_error(&file, &[]);
+        assert!(error.contains(&format!(
+            "{}:8:1: config.types.String has an unknown key 'defaul', did you mean 'default'?",
+            file.display()
+        )));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let file = dir.join(name);
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, content).unwrap();
+        file
+    }
+
+    #[test]
+    fn the_config_of_the_spec_comes_before_the_one_of_the_current_directory() {
+        let dir = test_dir("find");
+        let root = write(&dir, "api-gen.yaml", BASE);
+        let spec_config = write(&dir, "specs/orders/config.yaml", BASE);
+        let spec_dir = dir.join("specs/orders/v1");
+        let cwd = dir.join("tools");
+        std::fs::create_dir_all(&spec_dir).unwrap();
+        std::fs::create_dir_all(&cwd).unwrap();
+        assert_eq!(
+            nearest_config_file(&[spec_dir, cwd.clone()]),
+            Some(spec_config)
+        );
+        assert_eq!(nearest_config_file(&[dir.join("other"), cwd]), Some(root));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    const BASE: &str = "types:\n  string:\n    default: String\narrayLayout: List<{type}>\ntemplates: base-templates\nextended:\n  a: base\n  b: base\n";
+
+    #[test]
+    fn extended_files_are_deep_merged_beneath_the_file() {
+        let dir = test_dir("extends");
+        write(&dir, "shared/base.yaml", BASE);
+        let file = write(
+            &dir,
+            "config.yaml",
+            "extends: shared/base.yaml\narrayLayout: Vec<{type}>\nextended:\n  b: child\n",
+        );
+        let layered = LayeredConfig::load(Some(file.clone()), None, &[]).unwrap();
+        let config = layered.config().unwrap();
+        assert_eq!(config.array_layout, "Vec<{type}>");
+        assert_eq!(config.extended["a"], "base");
+        assert_eq!(config.extended["b"], "child");
+        assert_eq!(config.types["string"].default, "String");
+        // Paths are relative to the file that set them
+        assert_eq!(config.templates, dir.join("shared").join("base-templates"));
+        let shown = layered.show().unwrap();
+        assert!(shown.contains(&format!(
+            "a: base  # {}",
+            dir.join("shared/base.yaml").display()
+        )));
+        assert!(shown.contains(&format!("b: child  # {}", file.display())));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn a_file_extending_itself_is_an_error() {
+        let dir = test_dir("extends-cycle");
+        write(&dir, "a.yaml", "extends: b.yaml\n");
+        let file = write(&dir, "b.yaml", "extends: a.yaml\n");
+        let error = LayeredConfig::load(Some(file), None, &[]).err().unwrap();
+        assert!(error.to_string().contains("extends itself"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn environment_variables_and_set_override_the_files() {
+        let dir = test_dir("overrides");
+        let file = write(&dir, "config.yaml", BASE);
+        let env = [
+            (
+                "API_GEN_EXTENDED__layering_test".to_string(),
+                "env".to_string(),
+            ),
+            ("OTHER".to_string(), "ignored".to_string()),
+        ];
+        let overrides = [
+            "arrayLayout=Array<{type}>".to_string(),
+            "extended.b=cli".to_string(),
+            "templates=cli-templates".to_string(),
+        ];
+        let layered = LayeredConfig::load_with_env(Some(file), None, env, &overrides).unwrap();
+        let config = layered.config().unwrap();
+        assert_eq!(config.array_layout, "Array<{type}>");
+        assert_eq!(config.extended["a"], "base");
+        assert_eq!(config.extended["b"], "cli");
+        assert_eq!(config.extended["layering_test"], "env");
+        // Paths set by --set stay relative to the current directory
+        assert_eq!(config.templates, PathBuf::from("cli-templates"));
+        let shown = layered.show().unwrap();
+        assert!(shown.contains("layering_test: env  # env API_GEN_EXTENDED__layering_test"));
+        assert!(shown.contains("b: cli  # --set extended.b=cli"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn overrides_are_read_as_yaml_scalars() {
+        let dir = test_dir("scalars");
+        let file = write(&dir, "config.yaml", BASE);
+        let env = [("API_GEN_EXTENDED__retries".to_string(), "3".to_string())];
+        let overrides = [
+            "extended.nullSafety=true".to_string(),
+            "extended.version='1.0'".to_string(),
+            "optionalLayout={type}?".to_string(),
+        ];
+        let config = LayeredConfig::load_with_env(Some(file), None, env, &overrides)
+            .unwrap()
+            .config()
+            .unwrap();
+        assert_eq!(config.extended["retries"], serde_json::json!(3));
+        assert_eq!(config.extended["nullSafety"], serde_json::json!(true));
+        assert_eq!(config.extended["version"], serde_json::json!("1.0"));
+        assert_eq!(config.optional_layout.as_deref(), Some("{type}?"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn set_rejects_keys_only_allowed_in_files() {
+        let dir = test_dir("set-extends");
+        let file = write(&dir, "config.yaml", BASE);
+        for arg in [
+            "extends=other.yaml",
+            "pack.name=dart",
+            "arrayLayout",
+            "types..default=x",
+        ] {
+            assert!(LayeredConfig::load(Some(file.clone()), None, &[arg.to_string()]).is_err());
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn environment_variable_names_map_to_config_keys() {
+        let path = |name| env_key_path(name).map(|x| x.join("."));
+        assert_eq!(path("ARRAY_LAYOUT").as_deref(), Some("arrayLayout"));
+        assert_eq!(path("ARRAYLAYOUT").as_deref(), Some("arrayLayout"));
+        assert_eq!(
+            path("EXTENDED__feature").as_deref(),
+            Some("extended.feature")
+        );
+        assert_eq!(path("NAMING__escape").as_deref(), Some("naming.escape"));
+        assert_eq!(path("EXTENDS"), None);
+        assert_eq!(path("UNKNOWN"), None);
+        assert_eq!(path("EXTENDED__"), None);
     }
 }
@@ -1,53 +1,588 @@
-use simplelog::info;
-use std::{fs, path::PathBuf};
+use openapiv3::{OpenAPI, SchemaKind, Type};
+use simplelog::{info, warn};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
 
+use clap::ValueEnum;
+use include_dir::{include_dir, Dir};
 use lazy_static::lazy_static;
 
+use crate::{
+    cache::Cache,
+    config, get_open_api_content_and_doc,
+    schema_walker::{walk_schemas, SchemaNode},
+    serde_method::{integer_format, number_format, string_format},
+    source_location::locate_yaml,
+    terminal_setup, InitArgs, Preset,
+};
+
 lazy_static! {
     static ref CONFIG_FILE_NAME: std::path::PathBuf = PathBuf::from("config.yaml");
-    static ref MODEL_FILE_NAME: std::path::PathBuf = PathBuf::from("model.template");
-    static ref SERVICE_FILE_NAME: std::path::PathBuf = PathBuf::from("service.template");
     static ref TEMPLATE_FOLDER_NAME: std::path::PathBuf = PathBuf::from("templates");
 }
-// TODO use include_dir instead
-const CONFIG_FILE: &str = include_str!("../config_data/config.yaml");
-const MODEL_FILE: &str = include_str!("../config_data/model.template");
-const SERVICE_FILE: &str = include_str!("../config_data/service.template");
+/// The config and templates of each preset, in a folder named after it
+static PRESETS: Dir = include_dir!("$CARGO_MANIFEST_DIR/config_data/presets");
+
+/// The types of the config in the order they are written
+const TYPES: [&str; 4] = ["String", "Number", "Integer", "Boolean"];
+
+/// What `init` did with each file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileStatus {
+    Created,
+    Overwritten,
+    Merged,
+    Unchanged,
+    Skipped,
+}
+
+impl std::fmt::Display for FileStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            FileStatus::Created => "created",
+            FileStatus::Overwritten => "overwritten",
+            FileStatus::Merged => "merged",
+            FileStatus::Unchanged => "unchanged",
+            FileStatus::Skipped => "skipped",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// The name of the preset, which is its folder and its value on the command line
+fn preset_name(preset: Preset) -> &'static str {
+    match preset {
+        Preset::Rust => "rust",
+        Preset::Typescript => "typescript",
+        Preset::Python => "python",
+        Preset::Kotlin => "kotlin",
+        Preset::Go => "go",
+        Preset::Dart => "dart",
+        Preset::Karate => "karate",
+    }
+}
+
+/// The config file of the preset
+fn preset_config(preset: Preset) -> &'static str {
+    PRESETS
+        .get_file(format!("{}/config.yaml", preset_name(preset)))
+        .and_then(|x| x.contents_utf8())
+        .expect("Every preset has a config")
+}
+
+/// The templates of the preset with their path in the templates folder
+fn preset_templates(preset: Preset) -> Vec<(&'static Path, &'static str)> {
+    let folder = PathBuf::from(preset_name(preset)).join("templates");
+    let mut templates: Vec<_> = PRESETS
+        .get_dir(&folder)
+        .expect("Every preset has templates")
+        .files()
+        .map(|x| {
+            (
+                x.path().strip_prefix(&folder).unwrap(),
+                x.contents_utf8().expect("Templates are UTF-8"),
+            )
+        })
+        .collect();
+    templates.sort();
+    templates
+}
+
+/// Prints the presets with what their templates generate
+fn list_presets() {
+    for preset in Preset::value_variants() {
+        let help = preset
+            .to_possible_value()
+            .and_then(|x| x.get_help().map(|x| x.to_string()))
+            .unwrap_or_default();
+        println!("{:<11} {}", preset_name(*preset), help);
+    }
+}
 
 /// Initialise the config directory and files
-pub fn init() -> anyhow::Result<()> {
-    // TODO allow for templates for different languages
-    info!("Initialising config directory and files");
-    if CONFIG_FILE_NAME.exists() {
-        info!("Config file already exists");
-    } else {
-        info!("Creating config file");
-        fs::write(CONFIG_FILE_NAME.as_path(), CONFIG_FILE)?;
+pub fn init(args: InitArgs) -> anyhow::Result<()> {
+    if args.list {
+        list_presets();
+        return Ok(());
     }
+    terminal_setup(args.quiet, args.verbose)?;
+    info!(
+        "Initialising config directory and files for {}",
+        preset_name(args.preset)
+    );
+    let config = match &args.from_spec {
+        Some(api) => config_from_spec(api, preset_config(args.preset))?,
+        None => preset_config(args.preset).to_string(),
+    };
 
-    if TEMPLATE_FOLDER_NAME.exists() {
-        info!("Template folder already exists");
+    let mut report = Vec::new();
+    let status = if !CONFIG_FILE_NAME.exists() {
+        fs::write(CONFIG_FILE_NAME.as_path(), config)?;
+        FileStatus::Created
+    } else if args.force {
+        fs::write(CONFIG_FILE_NAME.as_path(), config)?;
+        FileStatus::Overwritten
+    } else if args.merge {
+        merge_config(CONFIG_FILE_NAME.as_path(), &config)?
     } else {
+        if args.from_spec.is_some() {
+            warn!("Config file already exists, the types found in the spec were not written");
+        }
+        FileStatus::Skipped
+    };
+    report.push((CONFIG_FILE_NAME.clone(), status));
+
+    if !TEMPLATE_FOLDER_NAME.exists() {
         info!("Creating template folder");
         fs::create_dir(TEMPLATE_FOLDER_NAME.as_path())?;
     }
 
-    let model = TEMPLATE_FOLDER_NAME.join(MODEL_FILE_NAME.as_path());
-    let service = TEMPLATE_FOLDER_NAME.join(SERVICE_FILE_NAME.as_path());
-    if model.exists() {
-        info!("Model template already exists");
-    } else {
-        info!("Creating model template");
-        fs::write(model.as_path(), MODEL_FILE)?;
+    for (name, contents) in preset_templates(args.preset) {
+        let path = TEMPLATE_FOLDER_NAME.join(name);
+        let status = if !path.exists() {
+            fs::write(&path, contents)?;
+            FileStatus::Created
+        } else if args.force {
+            fs::write(&path, contents)?;
+            FileStatus::Overwritten
+        } else {
+            FileStatus::Skipped
+        };
+        report.push((path, status));
     }
 
-    if service.exists() {
-        info!("Service template already exists");
-    } else {
-        info!("Creating service template");
-        fs::write(service.as_path(), SERVICE_FILE)?;
+    for (path, status) in &report {
+        match status {
+            FileStatus::Skipped => warn!("{:<11} {}", status.to_string(), path.display()),
+            _ => info!("{:<11} {}", status.to_string(), path.display()),
+        }
+    }
+    if report.iter().any(|(_, x)| *x == FileStatus::Skipped) {
+        warn!("Existing files were skipped, use --force to overwrite them or --merge to add the missing keys to the config");
     }
 
     info!("The template engine used is Tera (https://keats.github.io/tera/) and very similar to Jinja2, Django templates, Liquid and Twig.");
     Ok(())
 }
+
+/// Adds the keys of `config` that are missing from the existing config file, keeping its values,
+/// comments and key order
+fn merge_config(path: &Path, config: &str) -> anyhow::Result<FileStatus> {
+    let content = fs::read_to_string(path)?;
+    match merged_config(&content, config)
+        .map_err(|e| anyhow::anyhow!("Failed to merge into {}: {}", path.display(), e))?
+    {
+        Some(merged) => {
+            fs::write(path, merged)?;
+            Ok(FileStatus::Merged)
+        }
+        None => Ok(FileStatus::Unchanged),
+    }
+}
+
+/// The text of the existing config with the missing keys of `config` written at the end of the
+/// mapping they belong to, or `None` when it has them all. Keys missing from a flow mapping such
+/// as `{ default: String }` cannot be added without rewriting it, so the merge is refused
+fn merged_config(content: &str, config: &str) -> anyhow::Result<Option<String>> {
+    let mut existing: serde_yaml::Value = serde_yaml::from_str(content)?;
+    if existing.is_null() {
+        existing = serde_yaml::Value::Mapping(Default::default());
+    }
+    let new: serde_yaml::Value = serde_yaml::from_str(config)?;
+    let mut missing = Vec::new();
+    missing_keys(&existing, &new, &mut Vec::new(), &mut missing);
+    if missing.is_empty() {
+        return Ok(None);
+    }
+
+    let mut lines: Vec<&str> = content.lines().collect();
+    let unit = indent_unit(&lines);
+    let mut insertions = missing
+        .iter()
+        .map(|(parent, entries)| {
+            let (line, indent) = insertion_point(content, &lines, parent)?;
+            let text = serde_yaml::to_string(&serde_yaml::Value::Mapping(entries.clone()))?;
+            // serde_yaml indents with two spaces, which is changed to the indentation of the file
+            let text: Vec<String> = text
+                .lines()
+                .map(|x| {
+                    let depth = (x.len() - x.trim_start().len()) / 2;
+                    format!("{}{}", " ".repeat(indent + depth * unit), x.trim_start())
+                })
+                .collect();
+            Ok((line, parent.len(), text))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    // Inserting from the bottom keeps the lines found for the mappings above where they are, and
+    // the entries of a mapping ending on the same line as a nested one go after the nested ones
+    insertions.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    for (line, _, text) in &insertions {
+        lines.splice(*line..*line, text.iter().map(String::as_str));
+    }
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let merged = lines.join(newline) + newline;
+
+    add_missing_keys(&mut existing, &new);
+    if serde_yaml::from_str::<serde_yaml::Value>(&merged).ok() != Some(existing) {
+        return Err(anyhow::anyhow!(
+            "the missing keys could not be added without rewriting the file, add them by hand"
+        ));
+    }
+    Ok(Some(merged))
+}
+
+/// Collects the entries of `new` missing from `existing`, grouped by the key path of the
+/// mapping they are missing from
+fn missing_keys(
+    existing: &serde_yaml::Value,
+    new: &serde_yaml::Value,
+    path: &mut Vec<String>,
+    missing: &mut Vec<(Vec<String>, serde_yaml::Mapping)>,
+) {
+    let (Some(existing), Some(new)) = (existing.as_mapping(), new.as_mapping()) else {
+        return;
+    };
+    let mut entries = serde_yaml::Mapping::new();
+    for (key, value) in new {
+        match (existing.get(key), key.as_str()) {
+            (Some(current), Some(name)) => {
+                path.push(name.to_string());
+                missing_keys(current, value, path, missing);
+                path.pop();
+            }
+            (Some(_), None) => {}
+            (None, _) => {
+                entries.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    if !entries.is_empty() {
+        missing.push((path.clone(), entries));
+    }
+}
+
+/// The line the entries of the mapping at the path are added before and their indentation,
+/// after the last line of the mapping including the comments indented in it
+fn insertion_point(
+    content: &str,
+    lines: &[&str],
+    path: &[String],
+) -> anyhow::Result<(usize, usize)> {
+    if path.is_empty() {
+        return Ok((lines.len(), 0));
+    }
+    let position = locate_yaml(content, path)
+        .filter(|x| x.depth == path.len())
+        .ok_or_else(|| anyhow::anyhow!("'{}' was not found", path.join(".")))?;
+    let start = position.line - 1;
+    let column = position.column - 1;
+    let key_line = lines[start].split(" #").next().unwrap_or_default();
+    if !key_line.trim_end().ends_with(':') {
+        return Err(anyhow::anyhow!(
+            "'{}' is not a block mapping, add the missing keys to it by hand",
+            path.join(".")
+        ));
+    }
+    let mut end = start;
+    let mut indent = None;
+    for (i, line) in lines.iter().enumerate().skip(start + 1) {
+        let text = line.trim_start();
+        let depth = line.len() - text.len();
+        if text.is_empty() || (text.starts_with('#') && depth <= column) {
+            continue;
+        }
+        if depth <= column {
+            break;
+        }
+        if !text.starts_with('#') {
+            indent.get_or_insert(depth);
+        }
+        end = i;
+    }
+    Ok((end + 1, indent.unwrap_or(column + indent_unit(lines))))
+}
+
+/// The indentation of the first indented line, two spaces when there is none
+fn indent_unit(lines: &[&str]) -> usize {
+    lines
+        .iter()
+        .filter(|x| !x.trim().is_empty() && !x.trim_start().starts_with('#'))
+        .map(|x| x.len() - x.trim_start().len())
+        .find(|x| *x > 0)
+        .unwrap_or(2)
+}
+
+/// Recursively copies the keys of `new` that `existing` does not have, returning whether any were added
+fn add_missing_keys(existing: &mut serde_yaml::Value, new: &serde_yaml::Value) -> bool {
+    let (Some(existing), Some(new)) = (existing.as_mapping_mut(), new.as_mapping()) else {
+        return false;
+    };
+    let mut added = false;
+    for (key, value) in new {
+        match existing.get_mut(key) {
+            Some(current) => added |= add_missing_keys(current, value),
+            None => {
+                existing.insert(key.clone(), value.clone());
+                added = true;
+            }
+        }
+    }
+    added
+}
+
+/// Creates the config with `types` entries for every type and format used in the spec,
+/// mapped with the types of the preset config, warning about the schemas the generator
+/// cannot handle yet
+fn config_from_spec(api: &Path, preset_config: &str) -> anyhow::Result<String> {
+    if !api.exists() {
+        return Err(anyhow::anyhow!("OpenAPI file(s) not found"));
+    }
+    let contents = get_open_api_content_and_doc(&api.to_path_buf(), &mut Cache::disabled())?;
+    let doc: OpenAPI = serde_yaml::from_str(&contents)?;
+
+    let mut used: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    walk_schemas(&doc, &mut |path, schema| {
+        let schema = match schema {
+            SchemaNode::Item(schema) => schema,
+            SchemaNode::Reference => return,
+        };
+        let (name, format) = match &schema.schema_kind {
+            SchemaKind::Type(Type::String(x)) => ("String", string_format(&x.format)),
+            SchemaKind::Type(Type::Number(x)) => ("Number", number_format(&x.format)),
+            SchemaKind::Type(Type::Integer(x)) => ("Integer", integer_format(&x.format)),
+            SchemaKind::Type(Type::Boolean {}) => ("Boolean", None),
+            SchemaKind::Type(_) => return,
+            SchemaKind::OneOf { .. } => return unsupported(path, "oneOf"),
+            SchemaKind::AllOf { .. } => return unsupported(path, "allOf"),
+            SchemaKind::AnyOf { .. } => return unsupported(path, "anyOf"),
+            SchemaKind::Not { .. } => return unsupported(path, "not"),
+            SchemaKind::Any(_) => return unsupported(path, "a schema without a type"),
+        };
+        let formats = used.entry(name).or_default();
+        formats.extend(format);
+    });
+
+    if used.is_empty() {
+        warn!("No string, number, integer or boolean schemas found, the default types are used");
+        return Ok(preset_config.to_string());
+    }
+
+    let config: serde_yaml::Value = serde_yaml::from_str(preset_config)?;
+    let defaults: HashMap<String, config::Type> = serde_yaml::from_value(config["types"].clone())?;
+    info!("Adding the types and formats found in the spec");
+    let mut types = String::from("types:\n");
+    for name in TYPES.iter().filter(|x| used.contains_key(*x)) {
+        let default = defaults.get(*name);
+        let default_type = default.map_or(*name, |x| x.default.as_str());
+        types.push_str(&format!(
+            "    {}:\n        default: {}\n",
+            name,
+            config::yaml_scalar(default_type)
+        ));
+        if used[name].is_empty() {
+            continue;
+        }
+        types.push_str("        format:\n");
+        for format in &used[name] {
+            match default.and_then(|x| x.format.as_ref()?.get(format)) {
+                Some(mapped) => types.push_str(&format!("            {}: {}\n", format, mapped)),
+                None => types.push_str(&format!(
+                    "            {}: {} # Custom format, update to the appropriate type\n",
+                    format,
+                    config::yaml_scalar(default_type)
+                )),
+            }
+        }
+    }
+    Ok(replace_types(preset_config, &types))
+}
+
+fn unsupported(path: &str, kind: &str) {
+    warn!(
+        "{} uses {}, which the generator does not support yet",
+        path, kind
+    );
+}
+
+/// Replaces the `types` section of the default config, which ends at the next unindented line
+fn replace_types(config: &str, types: &str) -> String {
+    let mut output = String::new();
+    let mut in_types = false;
+    for line in config.lines() {
+        if line == "types:" {
+            in_types = true;
+            output.push_str(types);
+            continue;
+        }
+        if in_types && (line.starts_with(' ') || line.is_empty()) {
+            continue;
+        }
+        in_types = false;
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, generate, GenerateArgs};
+
+    /// An empty folder for the test in the temp folder
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("api-gen-init-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn every_preset_config_is_valid() {
+        for preset in Preset::value_variants() {
+            let config: Config = serde_yaml::from_str(preset_config(*preset))
+                .unwrap_or_else(|e| panic!("{}: {}", preset_name(*preset), e));
+            config
+                .validate()
+                .unwrap_or_else(|e| panic!("{}: {}", preset_name(*preset), e));
+        }
+    }
+
+    #[test]
+    fn every_preset_generates_from_the_petstore_fixture() {
+        let api = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures/petstore.yaml");
+        for preset in Preset::value_variants() {
+            let dir = test_dir(preset_name(*preset));
+            fs::write(dir.join("config.yaml"), preset_config(*preset)).unwrap();
+            fs::create_dir(dir.join("templates")).unwrap();
+            for (path, contents) in preset_templates(*preset) {
+                fs::write(dir.join("templates").join(path), contents).unwrap();
+            }
+            fs::create_dir(dir.join("output")).unwrap();
+            generate(GenerateArgs {
+                api: api.clone(),
+                output: Some(dir.join("output")),
+                config: Some(dir.join("config.yaml")),
+                verbose: false,
+                quiet: true,
+                sequential: false,
+                no_cache: true,
+                jobs: None,
+                set: vec!["extended.feature=Pets".to_string()],
+                report_format: None,
+                report: None,
+            })
+            .unwrap_or_else(|e| panic!("{}: {:?}", preset_name(*preset), e));
+            let generated: Vec<_> = fs::read_dir(dir.join("output"))
+                .unwrap()
+                .map(|x| x.unwrap().file_name())
+                .collect();
+            for (path, _) in preset_templates(*preset) {
+                let expected = if path.to_string_lossy().starts_with("model.") {
+                    // A model file is generated for the Pet model of the spec
+                    generated
+                        .iter()
+                        .any(|x| x.to_string_lossy().to_lowercase().starts_with("pet"))
+                } else {
+                    generated.iter().any(|x| x == path.as_os_str())
+                };
+                assert!(
+                    expected,
+                    "{}: {} generated nothing",
+                    preset_name(*preset),
+                    path.display()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn config_from_spec_uses_the_types_of_the_preset() {
+        let api = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures/petstore.yaml");
+        let config = config_from_spec(&api, preset_config(Preset::Rust)).unwrap();
+        let config: Config = serde_yaml::from_str(&config).unwrap();
+        assert_eq!(config.array_layout, "Vec<{type}>");
+        assert_eq!(config.types["Integer"].default, "i64");
+        let date_time = &config.types["String"].format.as_ref().unwrap()["DateTime"];
+        assert_eq!(date_time.target(), "DateTime<Utc>");
+        assert_eq!(date_time.import(), Some("chrono::{DateTime, Utc}"));
+
+        let config = config_from_spec(&api, preset_config(Preset::Karate)).unwrap();
+        let config: Config = serde_yaml::from_str(&config).unwrap();
+        assert_eq!(config.types["String"].default, "#string");
+    }
+
+    #[test]
+    fn merging_keeps_the_comments_and_order_of_the_config() {
+        let existing = "\
+# Shared with the mobile app
+arrayLayout: List<{type}>
+types:
+    # Dates are strings on the wire
+    String:
+        default: String
+        format:
+            Date: Date
+            # Email: String
+
+    Integer:
+        default: int
+";
+        let config = "\
+arrayLayout: Vec<{type}>
+optionalLayout: '{type}?'
+types:
+  String:
+    default: str
+    format:
+      Date: Date
+      DateTime: DateTime
+  Integer:
+    default: i64
+  Boolean:
+    default: bool
+";
+        let merged = merged_config(existing, config).unwrap().unwrap();
+        assert_eq!(
+            merged,
+            "\
+# Shared with the mobile app
+arrayLayout: List<{type}>
+types:
+    # Dates are strings on the wire
+    String:
+        default: String
+        format:
+            Date: Date
+            # Email: String
+            DateTime: DateTime
+
+    Integer:
+        default: int
+    Boolean:
+        default: bool
+optionalLayout: '{type}?'
+"
+        );
+        assert_eq!(merged_config(&merged, config).unwrap(), None);
+    }
+
+    #[test]
+    fn merging_into_a_flow_mapping_is_refused() {
+        let existing = "arrayLayout: List<{type}>\ntypes: { String: { default: String } }\n";
+        let config = "types:\n  Integer:\n    default: int\n";
+        let error = merged_config(existing, config).unwrap_err().to_string();
+        assert_eq!(
+            error,
+            "'types' is not a block mapping, add the missing keys to it by hand"
+        );
+    }
+}
@@ -0,0 +1,1187 @@
+pub mod cache;
+pub mod config;
+pub mod init;
+pub mod lint;
+mod naming;
+mod query;
+mod registry;
+mod report;
+mod schema_walker;
+pub mod serde_method;
+mod source_location;
+pub mod template_pack;
+mod tera_extensions;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::OsStr,
+    ops::AddAssign,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use cache::Cache;
+use clap::{Args, Subcommand, ValueEnum};
+
+use merge_yaml_hash::MergeYamlHash;
+use oapi::OApi;
+use openapiv3::{Parameter, RequestBody, Response};
+use rayon::prelude::*;
+use registry::ModelRegistry;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_method::DataStructure;
+use simplelog::{
+    debug, error, info, warn, Color, ColorChoice, ConfigBuilder, Level, LevelFilter, TermLogger,
+    TerminalMode,
+};
+use sppparse::SparseRoot;
+use tera::{Context, Tera};
+use tera_extensions::{
+    exists, extended, field_name, identifier, json_minimum_value, json_typing, json_value,
+    map_type_new, resolve_type, type_name, TypeImport,
+};
+use tera_text_filters::register_all;
+
+use crate::{
+    config::{parse_config_file, Config, LayeredConfig, TypeOverrides},
+    serde_method::serde_openapi,
+};
+
+/// Name the `modelFileName` from the config is registered under, so it can be rendered with a shared `Tera`
+const MODEL_FILE_NAME_TEMPLATE: &str = "__model_file_name";
+
+#[derive(Debug, Args, PartialEq, Eq)]
+pub struct GenerateArgs {
+    /// OpenAPI file(s) to generate from. It can be a folder
+    #[clap(short, long)]
+    pub api: PathBuf,
+    /// Output folder, defaults to `output` from the config
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+    /// Sets a custom config file, otherwise the nearest config file to the OpenAPI file(s) or the current directory is used
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Verbose mode (-v, -vv, -vvv, etc.)
+    #[clap(short, long)]
+    pub verbose: bool,
+    /// Quiet mode, only displays warnings and errors
+    #[clap(short, long)]
+    pub quiet: bool,
+    /// Generates files sequentially, one at a time instead of merging
+    #[clap(short, long)]
+    pub sequential: bool,
+    /// Ignores the cache and forces all files to be validated and rendered
+    #[clap(long)]
+    pub no_cache: bool,
+    /// Number of threads used to render files, defaults to the number of CPU cores
+    #[clap(short, long)]
+    pub jobs: Option<usize>,
+    /// Overrides a config value, e.g. `--set extended.feature=Orders`. Can be repeated
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+    /// Lints the OpenAPI document before generating and reports the diagnostics in this format. Only the rules set to `error` in the config stop the generation
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub report_format: Option<ReportFormat>,
+    /// File the lint report is written to, defaults to stdout
+    #[arg(long, value_name = "FILE", requires = "report_format")]
+    pub report: Option<PathBuf>,
+}
+
+#[derive(Debug, Args, PartialEq, Eq)]
+pub struct ValidateArgs {
+    /// OpenAPI file(s) to validate. It can be a folder
+    #[clap(short, long)]
+    pub api: PathBuf,
+    /// Sets a custom config file for the `lint` severities, otherwise the nearest config file is used if there is one
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Format the diagnostics are reported in. `text` logs them
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = ReportFormat::Text)]
+    pub report_format: ReportFormat,
+    /// File the report is written to, defaults to stdout
+    #[arg(long, value_name = "FILE")]
+    pub report: Option<PathBuf>,
+    /// Verbose mode (-v, -vv, -vvv, etc.)
+    #[clap(short, long)]
+    pub verbose: bool,
+    /// Quiet mode, only displays warnings and errors
+    #[clap(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Args, PartialEq, Eq)]
+pub struct ContextGenerateArgs {
+    /// OpenAPI file(s) to generate from. It can be a folder
+    #[clap(short, long, required_unless_present = "schema")]
+    pub api: Option<PathBuf>,
+    /// Sets a custom config file, otherwise the nearest config file to the OpenAPI file(s) or the current directory is used
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// File the context is written to, `-` for stdout. Defaults to `context.json` or `context.yaml`
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+    /// Format the context is written in
+    #[arg(short, long, value_enum, default_value_t = ContextFormat::Json)]
+    pub format: ContextFormat,
+    /// Indents the JSON output, the default
+    #[arg(long, conflicts_with = "compact")]
+    pub pretty: bool,
+    /// Writes the JSON output on a single line instead of indented, it cannot be used with `--format yaml`
+    #[arg(long)]
+    pub compact: bool,
+    /// Outputs the context a reserved template is rendered with instead of the full context
+    #[arg(long = "for", value_enum, value_name = "TEMPLATE")]
+    pub for_template: Option<ReservedTemplate>,
+    /// Name of the model, used with `--for model`
+    #[arg(long, required_if_eq("for_template", "model"))]
+    pub name: Option<String>,
+    /// Path of the endpoint, used with `--for model-endpoint`
+    #[arg(long, required_if_eq("for_template", "model-endpoint"))]
+    pub path: Option<String>,
+    /// Method of the endpoint, used with `--for model-endpoint`
+    #[arg(long, required_if_eq("for_template", "model-endpoint"))]
+    pub method: Option<String>,
+    /// Body of the endpoint, needed with `--for model-endpoint` when it has both a request and a response
+    #[arg(long, value_enum)]
+    pub body: Option<EndpointBody>,
+    /// Outputs the JSON Schema of the context instead of the context
+    #[arg(long, conflicts_with = "for_template")]
+    pub schema: bool,
+    /// Outputs only the values matching a JSONPath query, e.g. `$.endpoints[?(@.method=="post")].path`
+    #[arg(long, value_name = "QUERY")]
+    pub query: Option<String>,
+    /// Verbose mode (-v, -vv, -vvv, etc.)
+    #[clap(short, long)]
+    pub verbose: bool,
+    /// Quiet mode, only displays warnings and errors
+    #[clap(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ContextFormat {
+    Json,
+    Yaml,
+}
+
+/// The reserved templates that are rendered with their own context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReservedTemplate {
+    /// `model.` templates, rendered once per model
+    Model,
+    /// `model-endpoint.` templates, rendered once per request and response of each endpoint
+    ModelEndpoint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EndpointBody {
+    Request,
+    Response,
+}
+
+/// Formats the lint diagnostics can be reported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Logged with the other messages
+    Text,
+    /// One JSON object per line
+    Json,
+    /// SARIF 2.1.0, for code scanning tools
+    Sarif,
+}
+
+/// The languages `init` has a config and templates for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    /// Serde structs and a reqwest client
+    Rust,
+    /// Interfaces and a fetch client
+    Typescript,
+    /// Pydantic models and a requests client
+    Python,
+    /// kotlinx.serialization data classes and a Ktor client
+    Kotlin,
+    /// Structs with JSON tags and a net/http client
+    Go,
+    /// json_serializable classes and a service
+    Dart,
+    /// A Karate feature with a scenario for each endpoint
+    Karate,
+}
+
+#[derive(Debug, Args, PartialEq, Eq)]
+pub struct InitArgs {
+    /// Language the config and templates are written for
+    #[arg(long, value_enum, default_value_t = Preset::Dart)]
+    pub preset: Preset,
+    /// Lists the presets with what their templates generate
+    #[arg(long, exclusive = true)]
+    pub list: bool,
+    /// OpenAPI file(s) to pre-populate the `types` of the config from. It can be a folder
+    #[arg(long, value_name = "API")]
+    pub from_spec: Option<PathBuf>,
+    /// Overwrites the config and template files that already exist
+    #[arg(long, conflicts_with = "merge")]
+    pub force: bool,
+    /// Adds the keys missing from an existing config file, keeping its values
+    #[arg(long)]
+    pub merge: bool,
+    /// Verbose mode (-v, -vv, -vvv, etc.)
+    #[clap(short, long)]
+    pub verbose: bool,
+    /// Quiet mode, only displays warnings and errors
+    #[clap(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Subcommand, PartialEq, Eq)]
+pub enum ConfigCommands {
+    /// Outputs the JSON Schema of the config file
+    Schema,
+    /// Outputs the effective config and where each value came from
+    Show(ConfigShowArgs),
+}
+
+#[derive(Debug, Subcommand, PartialEq, Eq)]
+pub enum TemplateCommands {
+    /// Installs a template pack from a directory or a .tar.gz archive
+    Install(TemplateInstallArgs),
+    /// Lists the installed template packs
+    List,
+}
+
+#[derive(Debug, Args, PartialEq, Eq)]
+pub struct TemplateInstallArgs {
+    /// Directory or .tar.gz archive of the pack, containing a `pack.yaml` manifest and a `templates` folder
+    pub path: PathBuf,
+    /// Replaces the pack if the same name and version is already installed
+    #[clap(long)]
+    pub force: bool,
+    /// Verbose mode (-v, -vv, -vvv, etc.)
+    #[clap(short, long)]
+    pub verbose: bool,
+    /// Quiet mode, only displays warnings and errors
+    #[clap(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Debug, Args, PartialEq, Eq)]
+pub struct ConfigShowArgs {
+    /// Sets a custom config file
+    #[arg(short, long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+    /// Overrides a config value, e.g. `--set extended.feature=Orders`. Can be repeated
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Endpoint {
+    path: String,
+    method: String,
+    description: Option<String>,
+    parameters: Vec<Parameter>,
+    request: Option<RequestBody>,
+    response: Option<Response>,
+    extensions: BTreeMap<String, serde_json::Value>,
+}
+
+/// An operation of the spec
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+struct EndpointExtracted {
+    /// Path of the endpoint, e.g. `/orders/{id}`
+    path: String,
+    /// HTTP method of the endpoint, e.g. `post`
+    method: String,
+    description: Option<String>,
+    /// The OpenAPI parameters of the operation, as they are in the spec
+    #[schemars(with = "Vec<serde_json::Value>")]
+    parameters: Vec<Parameter>,
+    /// Model of the request body
+    request: Option<DataStructure>,
+    /// Model of the success response
+    response: Option<DataStructure>,
+    /// The response model and the objects and arrays nested in it, which reference the objects
+    /// nested in them by name
+    flat_response: Vec<DataStructure>,
+    /// The request model and the objects and arrays nested in it, which reference the objects
+    /// nested in them by name
+    flat_request: Vec<DataStructure>,
+    /// The `x-` vendor extensions of the operation
+    extensions: BTreeMap<String, serde_json::Value>,
+}
+
+/// The context templates other than the reserved `model.` and `model-endpoint.` templates are rendered with
+#[derive(Debug, Clone, Serialize, Default, JsonSchema)]
+pub struct TemplateData {
+    /// The URLs of the servers of the spec
+    base_url: Vec<String>,
+    endpoints: Vec<EndpointExtracted>,
+    /// The response models of all endpoints, with duplicates removed
+    responses: Vec<DataStructure>,
+    /// The request models of all endpoints, with duplicates removed
+    requests: Vec<DataStructure>,
+}
+
+impl TemplateData {
+    /// Applies the config to the models and flattens them, the preprocessing shared by `generate` and `context`
+    pub fn prepare(&mut self, config: &Config) -> anyhow::Result<()> {
+        self.apply_type_overrides(&TypeOverrides::new(&config.overrides)?);
+        self.apply_mapped_types(config);
+        self.flatten();
+        Ok(())
+    }
+
+    /// Sets the mapped type of the models of each endpoint, before they are flattened
+    pub fn apply_mapped_types(&mut self, config: &Config) {
+        for endpoint in &mut self.endpoints {
+            for model in [&mut endpoint.request, &mut endpoint.response]
+                .into_iter()
+                .flatten()
+            {
+                model.apply_mapped_types(config, false);
+            }
+        }
+    }
+
+    /// Sets the type overrides from the config on the models of each endpoint, before they are flattened
+    pub fn apply_type_overrides(&mut self, overrides: &TypeOverrides) {
+        for endpoint in &mut self.endpoints {
+            for model in [&mut endpoint.request, &mut endpoint.response]
+                .into_iter()
+                .flatten()
+            {
+                model.apply_type_overrides(overrides, &[]);
+            }
+        }
+    }
+
+    /// Flattens the models of each endpoint and combines them into the requests and responses,
+    /// which hold every model once with the objects nested in it referenced by name
+    pub fn flatten(&mut self) {
+        let mut requests = ModelRegistry::default();
+        let mut responses = ModelRegistry::default();
+        for endpoint in &mut self.endpoints {
+            if let Some(request) = &endpoint.request {
+                endpoint.flat_request = requests.flatten(request);
+            }
+            if let Some(response) = &endpoint.response {
+                endpoint.flat_response = responses.flatten(response);
+            }
+        }
+        self.requests = requests.into_models();
+        self.responses = responses.into_models();
+    }
+}
+
+impl From<Endpoint> for EndpointExtracted {
+    fn from(endpoint: Endpoint) -> Self {
+        Self {
+            path: endpoint.path,
+            method: endpoint.method,
+            description: endpoint.description,
+            parameters: endpoint.parameters,
+            request: None,
+            response: None,
+            flat_response: Vec::new(),
+            flat_request: Vec::new(),
+            extensions: endpoint.extensions,
+        }
+    }
+}
+
+fn terminal_setup(quiet: bool, verbose: bool) -> anyhow::Result<()> {
+    terminal_setup_with_mode(quiet, verbose, TerminalMode::Stdout)
+}
+
+/// Sets up the logger, using stderr when stdout is used for the output of the command
+fn terminal_setup_with_mode(quiet: bool, verbose: bool, mode: TerminalMode) -> anyhow::Result<()> {
+    let config = ConfigBuilder::new()
+        .set_level_color(Level::Debug, Some(Color::Cyan))
+        .set_level_color(Level::Info, Some(Color::Blue))
+        .set_level_color(Level::Warn, Some(Color::Yellow))
+        .set_level_color(Level::Error, Some(Color::Magenta))
+        .set_level_color(Level::Trace, Some(Color::Green))
+        .set_time_level(LevelFilter::Off)
+        .build();
+
+    if quiet && verbose {
+        return Err(anyhow::anyhow!(
+            "Cannot be quiet and verbose at the same time"
+        ));
+    }
+
+    let level = if quiet {
+        LevelFilter::Warn
+    } else if verbose {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Info
+    };
+
+    // The logger can only be set once, which is already the case when generating more than once in the same process
+    if TermLogger::init(level, config, mode, ColorChoice::Auto).is_err() {
+        debug!("Logger is already initialised");
+    }
+    Ok(())
+}
+
+pub fn generate_context(args: ContextGenerateArgs) -> anyhow::Result<()> {
+    let output = args.output.clone().unwrap_or_else(|| match args.format {
+        ContextFormat::Json => PathBuf::from("context.json"),
+        ContextFormat::Yaml => PathBuf::from("context.yaml"),
+    });
+    let to_stdout = output.as_os_str() == "-";
+    let mode = if to_stdout {
+        TerminalMode::Stderr
+    } else {
+        TerminalMode::Stdout
+    };
+    terminal_setup_with_mode(args.quiet, args.verbose, mode)?;
+    // Parsed first so a typo is reported before the spec is read
+    let query = args.query.as_deref().map(query::parse).transpose()?;
+    let mut context = match &args.api {
+        _ if args.schema => serde_json::to_value(schemars::schema_for!(TemplateData))?,
+        Some(api) => build_context(api, &args)?,
+        None => return Err(anyhow::anyhow!("No OpenAPI file(s), use --api")),
+    };
+    if let Some(query) = query {
+        let matches = query.select(&context).into_iter().cloned().collect();
+        context = serde_json::Value::Array(matches);
+    }
+    let mut serialized = match args.format {
+        ContextFormat::Json if args.compact => serde_json::to_string(&context)?,
+        ContextFormat::Json => serde_json::to_string_pretty(&context)?,
+        ContextFormat::Yaml => serde_yaml::to_string(&context)?,
+    };
+    if !serialized.ends_with('\n') {
+        serialized.push('\n');
+    }
+    if to_stdout {
+        print!("{}", serialized);
+    } else {
+        std::fs::write(&output, serialized)?;
+        info!("Context generated and saved to {:?}", output);
+    }
+    Ok(())
+}
+
+/// The full context, or the context of the reserved template selected by the args
+fn build_context(api: &Path, args: &ContextGenerateArgs) -> anyhow::Result<serde_json::Value> {
+    if !api.exists() {
+        return Err(anyhow::anyhow!("OpenAPI file(s) not found"));
+    }
+    let contents = get_open_api_content_and_doc(&api.to_path_buf(), &mut Cache::disabled())?;
+
+    let mut template = serde_openapi(contents)?;
+    let config = Arc::new(parse_config_file(args.config.clone(), Some(api), &[])?);
+    config.validate()?;
+    template.prepare(&config)?;
+    match args.for_template {
+        None => Ok(serde_json::to_value(&template)?),
+        Some(reserved) => reserved_template_context(&template, &config, reserved, args),
+    }
+}
+
+/// The context the reserved template is rendered with for the model or endpoint selected by the args,
+/// prepared the same way as when generating
+fn reserved_template_context(
+    template: &TemplateData,
+    config: &Arc<Config>,
+    reserved: ReservedTemplate,
+    args: &ContextGenerateArgs,
+) -> anyhow::Result<serde_json::Value> {
+    if config.model_file_name.is_none() {
+        return Err(anyhow::anyhow!(
+            "modelFileName is not set in config, it is needed for the file_name of reserved templates"
+        ));
+    }
+    let mut tera = Tera::default();
+    register_extensions(&mut tera, config)?;
+    let job = match reserved {
+        ReservedTemplate::Model => {
+            let name = args.name.as_deref().unwrap_or_default();
+            let model = template
+                .requests
+                .iter()
+                .chain(template.responses.iter())
+                .find(|x| x.object_name.as_deref() == Some(name) || x.name == name)
+                .ok_or_else(|| anyhow::anyhow!("Model '{}' not found", name))?;
+            prepare_model_file(model, &tera, config, Path::new(""), "")?
+        }
+        ReservedTemplate::ModelEndpoint => {
+            let path = args.path.as_deref().unwrap_or_default();
+            let method = args.method.as_deref().unwrap_or_default();
+            let endpoint = template
+                .endpoints
+                .iter()
+                .find(|x| x.path == path && x.method.eq_ignore_ascii_case(method))
+                .ok_or_else(|| anyhow::anyhow!("Endpoint {} {} not found", method, path))?;
+            let body = match args.body {
+                Some(body) => body,
+                None if endpoint.flat_response.is_empty() => EndpointBody::Request,
+                None if endpoint.flat_request.is_empty() => EndpointBody::Response,
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "Endpoint {} {} has a request and a response, use --body to pick one",
+                        method,
+                        path
+                    ))
+                }
+            };
+            let structure = match body {
+                EndpointBody::Request => &endpoint.flat_request,
+                EndpointBody::Response => &endpoint.flat_response,
+            };
+            if structure.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Endpoint {} {} has no {:?} body",
+                    method,
+                    path,
+                    body
+                ));
+            }
+            prepare_endpoint_model_file(structure, &tera, config, Path::new(""), "")?
+        }
+    };
+    let job = job.ok_or_else(|| {
+        anyhow::anyhow!("No file is rendered for it, arrays of primitives are skipped")
+    })?;
+    Ok(job.context.into_json())
+}
+
+/// Prints the JSON Schema of the config file so editors can validate and autocomplete it
+pub fn config_schema() -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(&config::schema())?);
+    Ok(())
+}
+
+/// Prints the merged config with the source of each value, then validates it
+pub fn config_show(args: ConfigShowArgs) -> anyhow::Result<()> {
+    let layered = LayeredConfig::load(args.config, None, &args.set)?;
+    print!("{}", layered.show()?);
+    layered.config()?;
+    Ok(())
+}
+
+/// The files `get_open_api_content_and_doc` reads the OpenAPI document(s) from
+pub(crate) fn spec_files(api: &Path) -> Vec<PathBuf> {
+    if api.is_file() {
+        let parent_path = api.parent().unwrap_or(Path::new(""));
+        let mut files = vec![api.to_path_buf()];
+        files.extend(
+            ["shared_models.yml", "shared_models.yaml"]
+                .iter()
+                .map(|x| parent_path.join(x))
+                .find(|x| x.exists()),
+        );
+        files
+    } else {
+        let mut files = find_files(api, OsStr::new("yml"));
+        files.append(&mut find_files(api, OsStr::new("yaml")));
+        files
+    }
+}
+
+/// Reads the OpenAPI document(s), merging them if needed, and validates the result
+pub fn get_open_api_content_and_doc(api: &PathBuf, cache: &mut Cache) -> anyhow::Result<String> {
+    let (contents, invalid) = read_open_api_content(api, cache)?;
+    match invalid {
+        Some(e) => {
+            error!("{}", e);
+            Err(anyhow::anyhow!("OpenAPI file not valid"))
+        }
+        None => Ok(contents),
+    }
+}
+
+/// Reads the OpenAPI document(s), merging them if needed, with the reason the result is not a
+/// valid OpenAPI document if it is not, so it can be reported with the lint diagnostics
+pub(crate) fn read_open_api_content(
+    api: &PathBuf,
+    cache: &mut Cache,
+) -> anyhow::Result<(String, Option<String>)> {
+    // Keeps the merged file alive until it has been parsed
+    let mut merged_file = None;
+    let (contents, path) = if api.is_file() {
+        let parent_path = api.parent().unwrap();
+        let shared_yml = parent_path.join("shared_models.yml");
+        let shared_yaml = parent_path.join("shared_models.yaml");
+        if shared_yml.exists() {
+            info!("Merging with shared_models.yml OpenAPI document");
+            let content = vec![
+                std::fs::read_to_string(api)?,
+                std::fs::read_to_string(shared_yml)?,
+            ];
+            let contents = merge(content);
+            let file = temp_file::with_contents(contents.as_bytes());
+            let path = file.path().to_path_buf();
+            merged_file = Some(file);
+            (contents, path)
+        } else if shared_yaml.exists() {
+            info!("Merging with shared_models.yaml OpenAPI document");
+            let content = vec![
+                std::fs::read_to_string(api)?,
+                std::fs::read_to_string(shared_yaml)?,
+            ];
+            let contents = merge(content);
+            let file = temp_file::with_contents(contents.as_bytes());
+            let path = file.path().to_path_buf();
+            merged_file = Some(file);
+            (contents, path)
+        } else {
+            let path = std::env::current_dir().unwrap().join(api);
+            (std::fs::read_to_string(path.clone())?, path)
+        }
+    } else {
+        let mut files = find_files(api, OsStr::new("yml"));
+        files.append(&mut find_files(api, OsStr::new("yaml")));
+        let mut content = Vec::new();
+        for file in files {
+            content.push(std::fs::read_to_string(file)?);
+        }
+        let contents = merge(content);
+        let file = temp_file::with_contents(contents.as_bytes());
+        let path = file.path().to_path_buf();
+        merged_file = Some(file);
+        debug!(
+            "Merged OpenAPI document has {} lines",
+            contents.lines().count()
+        );
+        (contents, path)
+    };
+
+    info!("Files merged");
+
+    let spec_hash = cache::hash(&[contents.as_bytes()]);
+    if cache.is_validated(&spec_hash) {
+        info!("OpenAPI document unchanged since it was last validated");
+        cache.mark_validated(spec_hash);
+        return Ok((contents, None));
+    }
+
+    info!("Parsing OpenAPI document");
+    let t = SparseRoot::new_from_file(path);
+    drop(merged_file);
+    let doc = match t {
+        Ok(t) => OApi::new(t),
+        Err(e) => return Ok((contents, Some(e.to_string()))),
+    };
+    info!("Checking if OpenAPI document is valid");
+    if let Err(e) = doc.check() {
+        return Ok((contents, Some(e.to_string())));
+    }
+    cache.mark_validated(spec_hash);
+    Ok((contents, None))
+}
+
+pub fn generate(args: GenerateArgs) -> anyhow::Result<()> {
+    match args.report_format {
+        Some(format) => terminal_setup_with_mode(
+            args.quiet,
+            args.verbose,
+            lint::report_terminal_mode(format, args.report.as_deref()),
+        )?,
+        None => terminal_setup(args.quiet, args.verbose)?,
+    }
+
+    if !args.api.exists() {
+        return Err(anyhow::anyhow!("OpenAPI file(s) not found"));
+    }
+
+    // A pool of its own, as the global one can only be configured once per process
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs.unwrap_or(0))
+        .build()?;
+
+    let config = Arc::new(parse_config_file(args.config, Some(&args.api), &args.set)?);
+    config.validate()?;
+    let output = args
+        .output
+        .or_else(|| config.output.clone())
+        .ok_or_else(|| {
+            anyhow::anyhow!("No output folder, use --output or set 'output' in the config")
+        })?;
+
+    if !config.templates.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Templates folder '{}' not found",
+            config.templates.display()
+        ));
+    }
+
+    // Kept in the output folder so it is found whichever directory the generator is run from
+    let mut cache = if args.no_cache {
+        info!("Cache disabled, generating all files");
+        Cache::disabled()
+    } else {
+        Cache::load(&output)
+    };
+
+    let (contents, invalid) = if args.sequential {
+        info!("Generating files sequentially");
+        let mut files = find_files(&args.api, OsStr::new("yml"));
+        files.append(&mut find_files(&args.api, OsStr::new("yaml")));
+        let mut contents = String::new();
+        let mut invalid = Vec::new();
+        for open_api_file in files {
+            info!("Generating file {:?}", open_api_file);
+            let (file_contents, problem) = read_open_api_content(&open_api_file, &mut cache)?;
+            contents.add_assign(file_contents.as_str());
+            invalid.extend(problem);
+        }
+        (contents, invalid)
+    } else {
+        let (contents, problem) = read_open_api_content(&args.api, &mut cache)?;
+        (contents, problem.into_iter().collect::<Vec<_>>())
+    };
+    let spec_hash = cache::hash(&[contents.as_bytes()]);
+
+    // Linted before the document is consumed, so the errors stop the generation
+    if let Some(format) = args.report_format {
+        lint::check(
+            &args.api,
+            &contents,
+            &invalid,
+            &config.lint,
+            false,
+            format,
+            args.report.as_deref(),
+        )?;
+    } else if !invalid.is_empty() {
+        for e in &invalid {
+            error!("{}", e);
+        }
+        return Err(anyhow::anyhow!("OpenAPI file not valid"));
+    }
+    let mut template = serde_openapi(contents)?;
+    template.prepare(&config)?;
+
+    let mut tera = match Tera::new(&format!("{}/**/*.*", config.templates.display())) {
+        Ok(t) => t,
+        Err(e) => {
+            error!("Parsing error(s): {}", e);
+            ::std::process::exit(1);
+        }
+    };
+    register_extensions(&mut tera, &config)?;
+    let context = Context::from_serialize(&template)?;
+
+    let template_dir = config.templates.as_path();
+    let files = get_files(template_dir);
+    // Partials can be included by any template so they are part of every template's hash
+    let shared_hash = cache::hash(&[
+        cache::hash_value(config.as_ref())?.as_bytes(),
+        hash_partials(template_dir)?.as_bytes(),
+    ]);
+
+    pool.install(|| -> anyhow::Result<()> {
+        for file in files {
+            let file_name = file.file_name().unwrap().to_str().unwrap();
+            let template_hash = cache::hash(&[shared_hash.as_bytes(), &std::fs::read(&file)?]);
+            if file_name.starts_with("model.") {
+                // Renders all models and outputs multiple files
+                info!("Rendering model files");
+                let jobs = template
+                    .requests
+                    .par_iter()
+                    .chain(template.responses.par_iter())
+                    .map(|model| prepare_model_file(model, &tera, &config, &output, &template_hash))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                render_files(
+                    jobs.into_iter().flatten().collect(),
+                    file_name,
+                    &tera,
+                    &mut cache,
+                )?;
+            } else if file_name.starts_with("model-endpoint.") {
+                // Renders all models and outputs multiple files
+                info!("Rendering model files");
+                let mut structures = Vec::new();
+                for endpoint in &template.endpoints {
+                    if !endpoint.flat_request.is_empty() && config.model_file_name.is_some() {
+                        structures.push(&endpoint.flat_request);
+                    } else if !endpoint.flat_request.is_empty() && config.model_file_name.is_none()
+                    {
+                        warn!("modelFileName is not set in config")
+                    }
+                    if !endpoint.flat_response.is_empty() && config.model_file_name.is_some() {
+                        structures.push(&endpoint.flat_response);
+                        // TODO setup a default is not set?
+                    } else if !endpoint.flat_response.is_empty() && config.model_file_name.is_none()
+                    {
+                        warn!("modelFileName is not set in config")
+                    }
+                }
+                let jobs = structures
+                    .par_iter()
+                    .map(|structure| {
+                        prepare_endpoint_model_file(
+                            structure,
+                            &tera,
+                            &config,
+                            &output,
+                            &template_hash,
+                        )
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                render_files(
+                    jobs.into_iter().flatten().collect(),
+                    file_name,
+                    &tera,
+                    &mut cache,
+                )?;
+            } else {
+                // Normal file render with full context
+                let output_file = output.join(file.file_name().unwrap());
+                let inputs_hash = cache::hash(&[template_hash.as_bytes(), spec_hash.as_bytes()]);
+                if cache.is_fresh(&output_file, &inputs_hash) {
+                    info!("File {:?} is unchanged", file_name);
+                } else {
+                    info!("Rendering file {:?}", file_name);
+                    let mut file_context = context.clone();
+                    file_context.insert("file_name", &file_name);
+                    let output = tera.render(file_name, &file_context)?;
+                    std::fs::write(&output_file, output)?;
+                }
+                cache.record(&output_file, inputs_hash);
+            }
+        }
+        Ok(())
+    })?;
+    cache.save()?;
+    Ok(())
+}
+
+/// Registers the filters and functions available to templates and the `modelFileName` template
+fn register_extensions(tera: &mut Tera, config: &Arc<Config>) -> anyhow::Result<()> {
+    register_all(tera);
+    if let Some(model_file_name) = &config.model_file_name {
+        tera.add_raw_template(MODEL_FILE_NAME_TEMPLATE, model_file_name)?;
+    }
+    tera.register_function("map_type", map_type_new(config.clone()));
+    tera.register_function("extended", extended(config.extended.clone()));
+    tera.register_function("exists", exists(config.extended.clone()));
+    tera.register_function("json_typing", json_typing(config.clone()));
+    let overrides = Arc::new(TypeOverrides::new(&config.overrides)?);
+    tera.register_function("json_value", json_value(overrides.clone()));
+    tera.register_function("json_min_value", json_minimum_value(overrides));
+    tera.register_filter("identifier", identifier(config.clone()));
+    tera.register_filter("field_name", field_name(config.clone()));
+    tera.register_filter("type_name", type_name(config.clone()));
+    Ok(())
+}
+
+/// A file rendered from a template with its own context
+struct RenderJob {
+    output_file_name: String,
+    output_file: PathBuf,
+    inputs_hash: String,
+    context: Context,
+}
+
+fn prepare_model_file(
+    structure: &DataStructure,
+    tera: &Tera,
+    config: &Config,
+    output_folder: &Path,
+    template_hash: &str,
+) -> anyhow::Result<Option<RenderJob>> {
+    // An array has the object name of its items, whose model is rendered instead
+    if structure.property_type == "Array" {
+        return Ok(None);
+    }
+    let mut context = Context::from_serialize(structure)?;
+    let output_file_name = tera.render(MODEL_FILE_NAME_TEMPLATE, &context)?;
+    let mut imports = BTreeSet::new();
+    for property in &structure.properties {
+        collect_imports(
+            property,
+            tera,
+            config,
+            Some(&output_file_name),
+            &mut imports,
+        )?;
+    }
+    context.insert("file_name", &output_file_name);
+    context.insert("imports", &imports);
+    let inputs_hash = cache::hash(&[
+        template_hash.as_bytes(),
+        cache::hash_value(structure)?.as_bytes(),
+    ]);
+    Ok(Some(RenderJob {
+        // TODO retain folder structure
+        output_file: output_folder.join(&output_file_name),
+        output_file_name,
+        inputs_hash,
+        context,
+    }))
+}
+
+fn prepare_endpoint_model_file(
+    structure: &Vec<DataStructure>,
+    tera: &Tera,
+    config: &Config,
+    output_folder: &Path,
+    template_hash: &str,
+) -> anyhow::Result<Option<RenderJob>> {
+    let root = structure
+        .iter()
+        .find(|x| x.is_root)
+        .ok_or_else(|| anyhow::anyhow!("The models of an endpoint have no root model"))?;
+    // TODO cater for nested arrays
+    if root.property_type == "Array" && root.properties[0].property_type != "Object" {
+        // Array of primitives
+        return Ok(None);
+    }
+    let root_model_context = Context::from_serialize(root)?;
+    let output_file_name = tera.render(MODEL_FILE_NAME_TEMPLATE, &root_model_context)?;
+    // All the models are in the same file so only the imports of the types are needed
+    let mut imports = BTreeSet::new();
+    for model in structure {
+        for property in &model.properties {
+            collect_imports(property, tera, config, None, &mut imports)?;
+        }
+    }
+    let mut context = Context::default();
+    context.insert("file_name", &output_file_name);
+    context.insert("models", &structure);
+    context.insert("imports", &imports);
+    let inputs_hash = cache::hash(&[
+        template_hash.as_bytes(),
+        cache::hash_value(structure)?.as_bytes(),
+    ]);
+    Ok(Some(RenderJob {
+        output_file: output_folder.join(&output_file_name),
+        output_file_name,
+        inputs_hash,
+        context,
+    }))
+}
+
+/// Collects the imports of the type the property is mapped to and, if the file of the model
+/// being rendered is given, the files of the other models it references
+fn collect_imports(
+    property: &DataStructure,
+    tera: &Tera,
+    config: &Config,
+    model_file: Option<&str>,
+    imports: &mut BTreeSet<String>,
+) -> anyhow::Result<()> {
+    let Some(resolved) = resolve_type(property, config) else {
+        return Ok(());
+    };
+    for import in resolved.imports {
+        match import {
+            TypeImport::Path(path) => {
+                imports.insert(path);
+            }
+            TypeImport::Model(model) => {
+                if let (Some(model_file), Some(_)) = (model_file, &config.model_file_name) {
+                    let file =
+                        tera.render(MODEL_FILE_NAME_TEMPLATE, &Context::from_serialize(model)?)?;
+                    if file != model_file {
+                        imports.insert(file);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Renders the files that changed in parallel and writes them in the order they were given
+fn render_files(
+    jobs: Vec<RenderJob>,
+    file_name: &str,
+    tera: &Tera,
+    cache: &mut Cache,
+) -> anyhow::Result<()> {
+    let mut fresh = Vec::with_capacity(jobs.len());
+    for job in &jobs {
+        debug!("Generated file name: {:#?}", job.output_file_name);
+        let is_fresh = cache.is_fresh(&job.output_file, &job.inputs_hash);
+        if is_fresh {
+            debug!("Model file {:?} is unchanged", job.output_file_name);
+        } else {
+            debug!("Context prepared: {:#?}", job.context);
+        }
+        cache.record(&job.output_file, job.inputs_hash.clone());
+        fresh.push(is_fresh);
+    }
+    let outputs = jobs
+        .par_iter()
+        .zip(fresh.par_iter())
+        .map(|(job, is_fresh)| {
+            if *is_fresh {
+                Ok(None)
+            } else {
+                tera.render(file_name, &job.context).map(Some)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for (job, output) in jobs.iter().zip(outputs) {
+        if let Some(output) = output {
+            debug!("Output rendered: {:?}", job.output_file_name);
+            std::fs::write(&job.output_file, output)?;
+        }
+    }
+    Ok(())
+}
+
+fn get_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for entry in path.read_dir().unwrap().flatten() {
+        if entry.path().is_dir() {
+            files.append(&mut get_files(&entry.path()));
+        } else if entry.path().is_file()
+            && !entry
+                .path()
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with('_')
+        {
+            files.push(entry.path().clone());
+        }
+    }
+    files
+}
+
+/// Hashes the partial templates (files starting with `_`) in a stable order
+fn hash_partials(path: &Path) -> anyhow::Result<String> {
+    let mut partials = Vec::new();
+    collect_partials(path, &mut partials);
+    partials.sort();
+    let mut contents = Vec::new();
+    for partial in &partials {
+        contents.push(partial.to_string_lossy().as_bytes().to_vec());
+        contents.push(std::fs::read(partial)?);
+    }
+    let parts: Vec<&[u8]> = contents.iter().map(|x| x.as_slice()).collect();
+    Ok(cache::hash(&parts))
+}
+
+fn collect_partials(path: &Path, partials: &mut Vec<PathBuf>) {
+    for entry in path.read_dir().unwrap().flatten() {
+        if entry.path().is_dir() {
+            collect_partials(&entry.path(), partials);
+        } else if entry.file_name().to_string_lossy().starts_with('_') {
+            partials.push(entry.path());
+        }
+    }
+}
+
+fn merge(files: Vec<String>) -> String {
+    let mut hash = MergeYamlHash::new();
+    debug!("Merging OpenAPI documents");
+    for file in files {
+        debug!("Merging file {:?}", file);
+        hash.merge(&file);
+    }
+
+    hash.to_string()
+}
+
+fn find_files(path: &std::path::Path, extension: &OsStr) -> Vec<PathBuf> {
+    debug!("Finding files in {:?}", path);
+    let mut files = Vec::new();
+    for entry in path.read_dir().expect("Failed to read directory").flatten() {
+        if entry.path().is_dir() {
+            debug!("Found directory {:?}", entry.path());
+            files.append(&mut find_files(&entry.path(), extension));
+        } else if entry.path().extension() == Some(extension) {
+            debug!("Found file {:?}", entry.path());
+            files.push(entry.path());
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty folder unique to the test
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("api-gen-lib-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn shared_models_are_merged_with_either_extension() {
+        for shared in ["shared_models.yml", "shared_models.yaml"] {
+            let dir = test_dir(shared);
+            let api = dir.join("api.yaml");
+            std::fs::write(
+                &api,
+                "openapi: 3.0.0\ninfo:\n  title: Api\n  version: 1.0.0\npaths: {}\n",
+            )
+            .unwrap();
+            std::fs::write(
+                dir.join(shared),
+                "components:\n  schemas:\n    Shared:\n      type: string\n",
+            )
+            .unwrap();
+            let (contents, _) = read_open_api_content(&api, &mut Cache::disabled()).unwrap();
+            assert!(contents.contains("Shared"), "{}: {}", shared, contents);
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn a_model_nested_in_one_endpoint_is_the_root_of_another() {
+        let spec = r##"
+openapi: 3.0.0
+info:
+  title: Api
+  version: 1.0.0
+paths:
+  /orders:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/Order"
+      responses:
+        "200":
+          description: Created
+  /addresses:
+    post:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/Address"
+      responses:
+        "200":
+          description: Created
+components:
+  schemas:
+    Order:
+      type: object
+      properties:
+        billing:
+          $ref: "#/components/schemas/Address"
+    Address:
+      type: object
+      properties:
+        street:
+          type: string
+"##;
+        let mut template = serde_openapi(spec.to_string()).unwrap();
+        template.flatten();
+        for endpoint in &template.endpoints {
+            let root: Vec<_> = endpoint.flat_request.iter().filter(|x| x.is_root).collect();
+            assert_eq!(root.len(), 1, "{}", endpoint.path);
+        }
+        let orders = &template.endpoints[0].flat_request;
+        assert_eq!(orders[1].name, "billing");
+        assert_eq!(template.requests.len(), 2);
+    }
+}
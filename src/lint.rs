@@ -0,0 +1,894 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    path::Path,
+};
+
+use openapiv3::{OpenAPI, ReferenceOr, SchemaKind, StatusCode, Type};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, ObjectValidation, Schema, SchemaObject},
+};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use simplelog::{info, warn, TerminalMode};
+
+use crate::{
+    cache::Cache,
+    config::{self, parse_config_file},
+    read_open_api_content, report,
+    schema_walker::{escape, walk_schemas, SchemaNode},
+    spec_files, terminal_setup_with_mode, ReportFormat, ValidateArgs,
+};
+
+/// Vendor extension on an operation or schema listing the rules that are not reported for it
+/// or anything in it
+pub const IGNORE_EXTENSION: &str = "x-api-gen-ignore";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Severity of a rule set in the `lint` section of the config
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleLevel {
+    Off,
+    Warn,
+    Error,
+}
+
+/// A problem found in the spec by a lint rule
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    /// JSON pointer to where the problem is in the spec
+    pub pointer: String,
+    pub message: String,
+}
+
+/// A lint rule with the severity it is reported with unless the config sets another one
+pub struct Rule {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub description: &'static str,
+    check: fn(&OpenAPI, &Value, &mut Vec<(String, String)>),
+}
+
+/// The lint rules run by `validate`, in the order they are reported
+pub const RULES: [Rule; 6] = [
+    Rule {
+        id: "duplicate-operation-id",
+        severity: Severity::Error,
+        description: "operationId is used by more than one operation",
+        check: duplicate_operation_id,
+    },
+    Rule {
+        id: "missing-success-response",
+        severity: Severity::Error,
+        description: "Operation has no 200 response, which the generator uses as the response model",
+        check: missing_success_response,
+    },
+    Rule {
+        id: "unsupported-schema",
+        severity: Severity::Error,
+        description: "Schema uses oneOf, allOf, anyOf, not or has no type, which the generator does not support yet",
+        check: unsupported_schema,
+    },
+    Rule {
+        id: "unresolved-ref",
+        severity: Severity::Error,
+        description: "Local $ref does not point to anything in the spec",
+        check: unresolved_ref,
+    },
+    Rule {
+        id: "object-name-collision",
+        severity: Severity::Error,
+        description: "Different objects get the same model name",
+        check: object_name_collision,
+    },
+    Rule {
+        id: "missing-description",
+        severity: Severity::Warning,
+        description: "Operation or component schema has no description",
+        check: missing_description,
+    },
+];
+
+/// Runs the lint rules on the spec with the severities from the config, skipping the rules
+/// that are off and the problems ignored with the `x-api-gen-ignore` extension
+pub fn lint(doc: &OpenAPI, value: &Value, levels: &HashMap<String, RuleLevel>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for rule in &RULES {
+        let severity = match levels.get(rule.id) {
+            Some(RuleLevel::Off) => continue,
+            Some(RuleLevel::Warn) => Severity::Warning,
+            Some(RuleLevel::Error) => Severity::Error,
+            None => rule.severity,
+        };
+        let mut found = Vec::new();
+        (rule.check)(doc, value, &mut found);
+        diagnostics.extend(
+            found
+                .into_iter()
+                .filter(|(pointer, _)| !is_ignored(value, pointer, rule.id))
+                .map(|(pointer, message)| Diagnostic {
+                    rule: rule.id,
+                    severity,
+                    pointer,
+                    message,
+                }),
+        );
+    }
+    diagnostics
+}
+
+/// Checks if the rule is listed in the `x-api-gen-ignore` extension of the node at the pointer
+/// or of any node containing it
+fn is_ignored(value: &Value, pointer: &str, rule: &str) -> bool {
+    let mut node = Some(value);
+    let mut tokens = pointer.split('/').skip(1);
+    while let Some(current) = node {
+        let ignored = current
+            .get(IGNORE_EXTENSION)
+            .and_then(Value::as_sequence)
+            .is_some_and(|rules| rules.iter().any(|x| x.as_str() == Some(rule)));
+        if ignored {
+            return true;
+        }
+        node = tokens.next().and_then(|token| {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            match current {
+                Value::Mapping(map) => map.get(token.as_str()),
+                Value::Sequence(items) => items.get(token.parse::<usize>().ok()?),
+                _ => None,
+            }
+        });
+    }
+    false
+}
+
+/// JSON Schema of the `lint` section of the config, with a key for every rule
+pub fn config_schema(_: &mut SchemaGenerator) -> Schema {
+    let properties = RULES
+        .iter()
+        .map(|rule| {
+            let default = match rule.severity {
+                Severity::Warning => "warn",
+                Severity::Error => "error",
+            };
+            let level = SchemaObject {
+                metadata: Some(Box::new(Metadata {
+                    description: Some(format!("{}. Defaults to `{}`", rule.description, default)),
+                    ..Default::default()
+                })),
+                instance_type: Some(InstanceType::String.into()),
+                enum_values: Some(vec!["off".into(), "warn".into(), "error".into()]),
+                ..Default::default()
+            };
+            (rule.id.to_string(), level.into())
+        })
+        .collect();
+    SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        object: Some(Box::new(ObjectValidation {
+            properties,
+            additional_properties: Some(Box::new(false.into())),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Validates the spec with `OApi::check` and the lint rules, failing if any error is found
+pub fn validate(args: ValidateArgs) -> anyhow::Result<()> {
+    terminal_setup_with_mode(
+        args.quiet,
+        args.verbose,
+        report_terminal_mode(args.report_format, args.report.as_deref()),
+    )?;
+    if !args.api.exists() {
+        return Err(anyhow::anyhow!("OpenAPI file(s) not found"));
+    }
+    // The config is optional, without one every rule has its default severity
+    let levels = match args
+        .config
+        .or_else(|| config::find_config_file(Some(&args.api)).ok())
+    {
+        Some(file) => {
+            let config = parse_config_file(Some(file), Some(&args.api), &[])?;
+            config.validate()?;
+            config.lint
+        }
+        None => HashMap::new(),
+    };
+    let (contents, invalid) = read_open_api_content(&args.api, &mut Cache::disabled())?;
+    check(
+        &args.api,
+        &contents,
+        invalid.as_slice(),
+        &levels,
+        true,
+        args.report_format,
+        args.report.as_deref(),
+    )
+}
+
+/// Logs to stderr when the report is written to stdout, so it stays parsable
+pub(crate) fn report_terminal_mode(format: ReportFormat, output: Option<&Path>) -> TerminalMode {
+    if format != ReportFormat::Text && output.is_none() {
+        TerminalMode::Stderr
+    } else {
+        TerminalMode::Stdout
+    }
+}
+
+/// Lints the merged spec and reports the diagnostics with their location in the spec files,
+/// failing if any of them is an error. `invalid` has the reasons `OApi::check` found the spec
+/// invalid, which are reported as `openapi-invalid` errors. Unless `strict`, only the errors of
+/// the rules set to `error` in the config and `openapi-invalid` fail
+pub(crate) fn check(
+    api: &Path,
+    contents: &str,
+    invalid: &[String],
+    levels: &HashMap<String, RuleLevel>,
+    strict: bool,
+    format: ReportFormat,
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    let mut diagnostics: Vec<Diagnostic> = invalid
+        .iter()
+        .map(|message| Diagnostic {
+            rule: report::OPENAPI_INVALID,
+            severity: Severity::Error,
+            pointer: String::new(),
+            message: message.clone(),
+        })
+        .collect();
+    // An invalid spec may not deserialize either, its problem is then the only one reported
+    match (
+        serde_yaml::from_str::<OpenAPI>(contents),
+        serde_yaml::from_str::<Value>(contents),
+    ) {
+        (Ok(doc), Ok(value)) => {
+            info!("Linting OpenAPI document");
+            diagnostics.extend(lint(&doc, &value, levels));
+        }
+        (Err(e), _) | (_, Err(e)) if diagnostics.is_empty() => return Err(e.into()),
+        _ => {}
+    }
+    report::write(&diagnostics, format, &spec_files(api), output)?;
+    let errors = diagnostics
+        .iter()
+        .filter(|x| x.severity == Severity::Error)
+        .count();
+    let failing = diagnostics.iter().any(|x| {
+        x.severity == Severity::Error
+            && (strict
+                || x.rule == report::OPENAPI_INVALID
+                || levels.get(x.rule) == Some(&RuleLevel::Error))
+    });
+    if failing {
+        return Err(anyhow::anyhow!(
+            "Validation failed with {} error(s) and {} warning(s)",
+            errors,
+            diagnostics.len() - errors
+        ));
+    }
+    if errors > 0 {
+        warn!(
+            "OpenAPI document has {} error(s) and {} warning(s), none from a rule set to `error` in the config",
+            errors,
+            diagnostics.len() - errors
+        );
+        return Ok(());
+    }
+    info!(
+        "OpenAPI document is valid with {} warning(s)",
+        diagnostics.len()
+    );
+    Ok(())
+}
+
+/// Calls `visit` with the JSON pointer and the operation of every operation in the spec
+fn operations<'a>(doc: &'a OpenAPI, visit: &mut dyn FnMut(String, &'a openapiv3::Operation)) {
+    for (path, item) in doc.paths.iter() {
+        if let Some(item) = item.as_item() {
+            for (method, operation) in item.iter() {
+                visit(format!("/paths/{}/{}", escape(path), method), operation);
+            }
+        }
+    }
+}
+
+fn duplicate_operation_id(doc: &OpenAPI, _: &Value, found: &mut Vec<(String, String)>) {
+    let mut ids: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    operations(doc, &mut |pointer, operation| {
+        if let Some(id) = &operation.operation_id {
+            ids.entry(id).or_default().push(pointer);
+        }
+    });
+    for (id, pointers) in ids.iter().filter(|(_, x)| x.len() > 1) {
+        for pointer in pointers {
+            found.push((
+                format!("{}/operationId", pointer),
+                format!(
+                    "operationId '{}' is used by {} operations",
+                    id,
+                    pointers.len()
+                ),
+            ));
+        }
+    }
+}
+
+fn missing_success_response(doc: &OpenAPI, _: &Value, found: &mut Vec<(String, String)>) {
+    operations(doc, &mut |pointer, operation| {
+        let responses = &operation.responses.responses;
+        if responses.contains_key(&StatusCode::Code(200)) {
+            return;
+        }
+        let success = responses.keys().find(|status| match status {
+            StatusCode::Code(code) => (200..300).contains(code),
+            StatusCode::Range(range) => *range == 2,
+        });
+        let message = match success {
+            Some(status) => format!(
+                "has a {} response but no 200 response, which the generator uses as the response model",
+                status
+            ),
+            None => "has no 2xx response".to_string(),
+        };
+        found.push((format!("{}/responses", pointer), message));
+    });
+}
+
+fn unsupported_schema(doc: &OpenAPI, _: &Value, found: &mut Vec<(String, String)>) {
+    walk_schemas(doc, &mut |pointer, schema| {
+        let SchemaNode::Item(schema) = schema else {
+            return;
+        };
+        let kind = match &schema.schema_kind {
+            SchemaKind::OneOf { .. } => "oneOf",
+            SchemaKind::AllOf { .. } => "allOf",
+            SchemaKind::AnyOf { .. } => "anyOf",
+            SchemaKind::Not { .. } => "not",
+            SchemaKind::Any(_) => "a schema without a type",
+            SchemaKind::Type(_) => return,
+        };
+        found.push((
+            pointer.to_string(),
+            format!("uses {}, which the generator does not support yet", kind),
+        ));
+    });
+}
+
+fn unresolved_ref(_: &OpenAPI, value: &Value, found: &mut Vec<(String, String)>) {
+    let mut references = Vec::new();
+    collect_refs(value, String::new(), &mut references);
+    for (pointer, reference) in references {
+        // References to other files are resolved when the files are merged
+        let Some(target) = reference.strip_prefix('#') else {
+            continue;
+        };
+        if resolve(value, target).is_none() {
+            found.push((
+                format!("{}/$ref", pointer),
+                format!("'{}' does not resolve to anything in the spec", reference),
+            ));
+        }
+    }
+}
+
+fn collect_refs(value: &Value, pointer: String, references: &mut Vec<(String, String)>) {
+    match value {
+        Value::Mapping(map) => {
+            for (key, item) in map {
+                let key = key.as_str().map(String::from).unwrap_or_else(|| {
+                    serde_yaml::to_string(key)
+                        .map(|x| x.trim_end().to_string())
+                        .unwrap_or_default()
+                });
+                match (key.as_str(), item) {
+                    ("$ref", Value::String(reference)) => {
+                        references.push((pointer.clone(), reference.clone()))
+                    }
+                    _ => collect_refs(item, format!("{}/{}", pointer, escape(&key)), references),
+                }
+            }
+        }
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_refs(item, format!("{}/{}", pointer, i), references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolves a JSON pointer, e.g. `/components/schemas/Pet`
+fn resolve<'a>(value: &'a Value, pointer: &str) -> Option<&'a Value> {
+    if pointer.is_empty() {
+        return Some(value);
+    }
+    pointer
+        .strip_prefix('/')?
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .try_fold(value, |value, token| match value {
+            Value::Mapping(map) => map.get(token.as_str()),
+            Value::Sequence(items) => items.get(token.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+fn object_name_collision(doc: &OpenAPI, _: &Value, found: &mut Vec<(String, String)>) {
+    let mut objects: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    walk_schemas(doc, &mut |pointer, schema| {
+        let SchemaNode::Item(schema) = schema else {
+            return;
+        };
+        if !matches!(schema.schema_kind, SchemaKind::Type(Type::Object(_))) {
+            return;
+        }
+        let Some(name) = object_name(pointer, schema) else {
+            return;
+        };
+        let definition = serde_json::to_string(schema).unwrap_or_default();
+        objects
+            .entry(name)
+            .or_default()
+            .push((pointer.to_string(), definition));
+    });
+    let mut names: Vec<_> = objects.into_iter().collect();
+    names.sort();
+    for (name, definitions) in names {
+        let first = &definitions[0].1;
+        if definitions.iter().all(|(_, x)| x == first) {
+            continue;
+        }
+        let pointers: Vec<&str> = definitions.iter().map(|(x, _)| x.as_str()).collect();
+        for (pointer, _) in &definitions {
+            found.push((
+                pointer.clone(),
+                format!(
+                    "is named '{}' like the different objects at {}",
+                    name,
+                    pointers
+                        .iter()
+                        .filter(|x| **x != pointer.as_str())
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+        }
+    }
+}
+
+/// The model name the generator gives the object at the pointer: its `x-class-name`, the name
+/// of the component it is, or `{name}Object` after the property or array it is defined in
+fn object_name(pointer: &str, schema: &openapiv3::Schema) -> Option<String> {
+    if let Some(class_name) = schema
+        .schema_data
+        .extensions
+        .get("x-class-name")
+        .and_then(|x| x.as_str())
+    {
+        return Some(class_name.to_string());
+    }
+    let tokens: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    match tokens.as_slice() {
+        [components, schemas, name] if components == "components" && schemas == "schemas" => {
+            Some(name.clone())
+        }
+        _ => schema_name(&tokens).map(|name| format!("{}Object", name)),
+    }
+}
+
+/// The name of the schema at the tokens, from the property or component it is in, with an
+/// `Item` suffix for each array it is the items of
+fn schema_name(tokens: &[String]) -> Option<String> {
+    match tokens {
+        [.., parent, name] if parent == "properties" || parent == "schemas" => Some(name.clone()),
+        [rest @ .., items] if items == "items" => {
+            schema_name(rest).map(|name| format!("{}Item", name))
+        }
+        _ => None,
+    }
+}
+
+fn missing_description(doc: &OpenAPI, _: &Value, found: &mut Vec<(String, String)>) {
+    operations(doc, &mut |pointer, operation| {
+        if operation.description.is_none() && operation.summary.is_none() {
+            found.push((
+                pointer,
+                "operation has no description or summary".to_string(),
+            ));
+        }
+    });
+    if let Some(components) = &doc.components {
+        for (name, schema) in &components.schemas {
+            if let ReferenceOr::Item(schema) = schema {
+                if schema.schema_data.description.is_none() {
+                    found.push((
+                        format!("/components/schemas/{}", escape(name)),
+                        "schema has no description".to_string(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r##"
+openapi: 3.0.0
+info:
+  title: Shop
+  version: "1"
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      summary: Lists the pets
+      responses:
+        "200":
+          description: ok
+          content:
+            application/json:
+              schema:
+                $ref: "#/components/schemas/Pets"
+    post:
+      operationId: listPets
+      description: Adds a pet
+      responses:
+        "201":
+          description: created
+  /stores/{id}:
+    delete:
+      x-api-gen-ignore: [missing-description]
+      operationId: deleteStore
+      parameters:
+        - name: id
+          in: path
+          required: true
+          schema:
+            type: string
+      responses:
+        "404":
+          description: not found
+components:
+  schemas:
+    Pet:
+      description: A pet
+      type: object
+      properties:
+        owner:
+          type: object
+          properties:
+            name:
+              type: string
+        kind:
+          oneOf:
+            - type: string
+            - type: integer
+        store:
+          $ref: "#/components/schemas/Store"
+    Pets:
+      type: array
+      items:
+        $ref: "#/components/schemas/Pet"
+    Order:
+      description: An order
+      x-api-gen-ignore: [unsupported-schema]
+      type: object
+      properties:
+        owner:
+          type: object
+          properties:
+            id:
+              type: integer
+        note:
+          anyOf:
+            - type: string
+"##;
+
+    fn run(levels: &[(&str, RuleLevel)]) -> Vec<(&'static str, Severity, String)> {
+        let doc: OpenAPI = serde_yaml::from_str(SPEC).unwrap();
+        let value: Value = serde_yaml::from_str(SPEC).unwrap();
+        let levels = levels
+            .iter()
+            .map(|(rule, level)| (rule.to_string(), *level))
+            .collect();
+        lint(&doc, &value, &levels)
+            .into_iter()
+            .map(|x| (x.rule, x.severity, x.pointer))
+            .collect()
+    }
+
+    fn pointers(diagnostics: &[(&str, Severity, String)], rule: &str) -> Vec<String> {
+        diagnostics
+            .iter()
+            .filter(|(x, _, _)| *x == rule)
+            .map(|(_, _, pointer)| pointer.clone())
+            .collect()
+    }
+
+    #[test]
+    fn every_rule_finds_its_problems() {
+        let diagnostics = run(&[]);
+        assert_eq!(
+            pointers(&diagnostics, "duplicate-operation-id"),
+            [
+                "/paths/~1pets/get/operationId",
+                "/paths/~1pets/post/operationId"
+            ]
+        );
+        assert_eq!(
+            pointers(&diagnostics, "missing-success-response"),
+            [
+                "/paths/~1pets/post/responses",
+                "/paths/~1stores~1{id}/delete/responses"
+            ]
+        );
+        assert_eq!(
+            pointers(&diagnostics, "unsupported-schema"),
+            ["/components/schemas/Pet/properties/kind"]
+        );
+        assert_eq!(
+            pointers(&diagnostics, "unresolved-ref"),
+            ["/components/schemas/Pet/properties/store/$ref"]
+        );
+        assert_eq!(
+            pointers(&diagnostics, "object-name-collision"),
+            [
+                "/components/schemas/Pet/properties/owner",
+                "/components/schemas/Order/properties/owner"
+            ]
+        );
+        assert_eq!(
+            pointers(&diagnostics, "missing-description"),
+            ["/components/schemas/Pets"]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|(rule, severity, _)| (*rule == "missing-description")
+                == (*severity == Severity::Warning)));
+    }
+
+    #[test]
+    fn levels_from_the_config_override_the_default_severity() {
+        let diagnostics = run(&[
+            ("duplicate-operation-id", RuleLevel::Off),
+            ("unresolved-ref", RuleLevel::Warn),
+            ("missing-description", RuleLevel::Error),
+        ]);
+        assert!(pointers(&diagnostics, "duplicate-operation-id").is_empty());
+        let severity = |rule: &str| {
+            diagnostics
+                .iter()
+                .find(|(x, _, _)| *x == rule)
+                .map(|(_, severity, _)| *severity)
+        };
+        assert_eq!(severity("unresolved-ref"), Some(Severity::Warning));
+        assert_eq!(severity("missing-description"), Some(Severity::Error));
+        assert_eq!(severity("unsupported-schema"), Some(Severity::Error));
+    }
+
+    #[test]
+    fn ignore_extension_applies_to_the_node_and_its_children() {
+        let value: Value = serde_yaml::from_str(SPEC).unwrap();
+        let order = "/components/schemas/Order/properties/note";
+        assert!(is_ignored(&value, order, "unsupported-schema"));
+        assert!(!is_ignored(&value, order, "missing-description"));
+        assert!(is_ignored(
+            &value,
+            "/paths/~1stores~1{id}/delete",
+            "missing-description"
+        ));
+        assert!(!is_ignored(
+            &value,
+            "/paths/~1pets/get",
+            "missing-description"
+        ));
+    }
+
+    #[test]
+    fn object_names_come_from_the_class_name_component_property_or_array() {
+        let object: openapiv3::Schema =
+            serde_yaml::from_str("type: object\nproperties: {}\n").unwrap();
+        let name = |pointer| object_name(pointer, &object);
+        assert_eq!(name("/components/schemas/Pet").as_deref(), Some("Pet"));
+        assert_eq!(
+            name("/components/schemas/Pet/properties/owner").as_deref(),
+            Some("ownerObject")
+        );
+        assert_eq!(
+            name("/components/schemas/Pet/properties/tags/items").as_deref(),
+            Some("tagsItemObject")
+        );
+        assert_eq!(
+            name("/components/schemas/Pets/items/items").as_deref(),
+            Some("PetsItemItemObject")
+        );
+        assert_eq!(name("/paths/~1pets/get/responses/200"), None);
+
+        let named: openapiv3::Schema =
+            serde_yaml::from_str("type: object\nx-class-name: Owner\n").unwrap();
+        assert_eq!(
+            object_name("/components/schemas/Pet/properties/owner", &named).as_deref(),
+            Some("Owner")
+        );
+    }
+
+    #[test]
+    fn a_component_and_a_property_of_the_same_name_do_not_collide() {
+        let spec = r##"
+openapi: 3.0.0
+info:
+  title: Shop
+  version: "1"
+paths: {}
+components:
+  schemas:
+    Order:
+      type: object
+      properties:
+        item:
+          type: object
+          properties:
+            quantity:
+              type: integer
+        shipping:
+          type: object
+          x-class-name: Address
+          properties:
+            street:
+              type: string
+    item:
+      type: object
+      properties:
+        sku:
+          type: string
+    Address:
+      type: object
+      properties:
+        city:
+          type: string
+"##;
+        let doc: OpenAPI = serde_yaml::from_str(spec).unwrap();
+        let value: Value = serde_yaml::from_str(spec).unwrap();
+        let mut found = Vec::new();
+        object_name_collision(&doc, &value, &mut found);
+        let pointers: Vec<&str> = found.iter().map(|(x, _)| x.as_str()).collect();
+        assert_eq!(
+            pointers,
+            [
+                "/components/schemas/Order/properties/shipping",
+                "/components/schemas/Address"
+            ]
+        );
+    }
+
+    #[test]
+    fn pointers_resolve_escaped_tokens_and_indexes() {
+        let value: Value = serde_yaml::from_str(SPEC).unwrap();
+        assert_eq!(
+            resolve(&value, "/paths/~1stores~1{id}/delete/parameters/0/name"),
+            Some(&Value::from("id"))
+        );
+        assert_eq!(resolve(&value, ""), Some(&value));
+        assert_eq!(resolve(&value, "/components/schemas/Store"), None);
+    }
+
+    #[test]
+    fn check_reports_openapi_errors_and_fails() {
+        let dir = std::env::temp_dir().join(format!("api-gen-lint-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let api = dir.join("api.yaml");
+        std::fs::write(&api, SPEC).unwrap();
+        let report = dir.join("report.json");
+        let invalid = ["Parameter `{id}` is not present in path".to_string()];
+        let levels = RULES
+            .iter()
+            .map(|rule| (rule.id.to_string(), RuleLevel::Off))
+            .collect();
+
+        let result = check(
+            &api,
+            SPEC,
+            &invalid,
+            &levels,
+            false,
+            ReportFormat::Json,
+            Some(&report),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Validation failed with 1 error(s) and 0 warning(s)"
+        );
+        let line: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+        assert_eq!(line["rule"], "openapi-invalid");
+        assert_eq!(line["severity"], "error");
+        assert_eq!(line["message"], invalid[0].as_str());
+
+        // The problem of a spec that does not deserialize is still reported
+        let result = check(
+            &api,
+            "openapi: [",
+            &invalid,
+            &levels,
+            false,
+            ReportFormat::Json,
+            Some(&report),
+        );
+        assert!(result.is_err());
+        assert!(std::fs::read_to_string(&report)
+            .unwrap()
+            .contains("openapi-invalid"));
+
+        assert!(check(
+            &api,
+            SPEC,
+            &[],
+            &levels,
+            true,
+            ReportFormat::Json,
+            Some(&report)
+        )
+        .is_ok());
+        assert_eq!(std::fs::read_to_string(&report).unwrap(), "");
+    }
+
+    #[test]
+    fn only_rules_set_to_error_fail_unless_strict() {
+        let dir = std::env::temp_dir().join(format!("api-gen-lint-strict-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let api = dir.join("api.yaml");
+        std::fs::write(&api, SPEC).unwrap();
+        let report = dir.join("report.json");
+        let check = |levels: &HashMap<String, RuleLevel>, strict| {
+            check(
+                &api,
+                SPEC,
+                &[],
+                levels,
+                strict,
+                ReportFormat::Json,
+                Some(&report),
+            )
+        };
+
+        let defaults = HashMap::new();
+        assert!(check(&defaults, true).is_err());
+        assert!(check(&defaults, false).is_ok());
+        let explicit = HashMap::from([("unresolved-ref".to_string(), RuleLevel::Error)]);
+        assert!(check(&explicit, false).is_err());
+        let explicit = HashMap::from([("unresolved-ref".to_string(), RuleLevel::Off)]);
+        assert!(check(&explicit, false).is_ok());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::config::{Case, Escape, Naming};
+
+/// Makes the name a valid identifier: characters that are not letters, digits or `_` are
+/// replaced with `_`, a leading digit is prefixed with `escapeWith` and reserved words are escaped
+pub fn identifier(name: &str, naming: &Naming) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if identifier.is_empty() {
+        return naming.escape_with.clone();
+    }
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier = format!("{}{}", naming.escape_with, identifier);
+    }
+    if naming.reserved_words.contains(&identifier) {
+        identifier = match naming.escape {
+            Escape::Prefix => format!("{}{}", naming.escape_with, identifier),
+            Escape::Suffix => format!("{}{}", identifier, naming.escape_with),
+            Escape::Raw => format!("r#{}", identifier),
+        };
+    }
+    identifier
+}
+
+/// Converts the name to the `fieldCase` and makes it a valid identifier
+pub fn field_name(name: &str, naming: &Naming) -> String {
+    identifier(&convert_case(name, naming.field_case), naming)
+}
+
+/// Converts the name to the `typeCase` and makes it a valid identifier
+pub fn type_name(name: &str, naming: &Naming) -> String {
+    identifier(&convert_case(name, naming.type_case), naming)
+}
+
+fn convert_case(name: &str, case: Option<Case>) -> String {
+    let case = match case {
+        Some(case) => case,
+        None => return name.to_string(),
+    };
+    let words = split_words(name);
+    match case {
+        Case::Camel => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                if i == 0 {
+                    word.to_lowercase()
+                } else {
+                    capitalize(word)
+                }
+            })
+            .collect(),
+        Case::Pascal => words.iter().map(|word| capitalize(word)).collect(),
+        Case::Snake => words
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>()
+            .join("_"),
+        Case::ScreamingSnake => words
+            .iter()
+            .map(|word| word.to_uppercase())
+            .collect::<Vec<_>>()
+            .join("_"),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Splits on characters that are not letters or digits and on case changes,
+/// e.g. `userID`, `user-id` and `HTTPServer2Name` into `user ID`, `user id` and `HTTP Server2 Name`
+fn split_words(name: &str) -> Vec<String> {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if c.is_uppercase() && !current.is_empty() {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|x| x.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
@@ -0,0 +1,464 @@
+use std::cmp::Ordering;
+
+use serde_json::Value;
+
+/// A JSONPath query, parsed by [`parse`]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+enum Segment {
+    /// `.name` or `['name']`
+    Child(String),
+    /// `[0]`, negative indexes count from the end
+    Index(i64),
+    /// `.*`, `[*]` or `[]`
+    Wildcard,
+    /// `..`, the node and all of its descendants, followed by the selector applied to them
+    Descendants,
+    /// `[?(@.path == value)]` or `[?(@.path)]`
+    Filter(Filter),
+}
+
+struct Filter {
+    path: Vec<Segment>,
+    comparison: Option<(Comparison, Value)>,
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Parses the subset of JSONPath supported by `context --query`: `$`, `.name`, `['name']`, `[0]`,
+/// `*`, `..` and filters comparing a relative path with `==`, `!=`, `<`, `<=`, `>` or `>=`.
+/// Like jq, the query can also start with `.` instead of `$` and `[]` selects every item
+pub fn parse(query: &str) -> anyhow::Result<Query> {
+    let query = query.trim();
+    let rest = match query.strip_prefix('$') {
+        Some(rest) => rest,
+        None if query.starts_with('.') || query.starts_with('[') => query,
+        None => {
+            return Err(anyhow::anyhow!(
+                "Invalid query '{}', it must start with '$' or '.'",
+                query
+            ))
+        }
+    };
+    // A lone `.` is the root in jq
+    let rest = if rest == "." { "" } else { rest };
+    let mut parser = Parser {
+        chars: rest.chars().collect(),
+        pos: 0,
+    };
+    let segments = parser
+        .segments(false)
+        .map_err(|e| anyhow::anyhow!("Invalid query '{}': {}", query, e))?;
+    Ok(Query { segments })
+}
+
+impl Query {
+    /// The values matching the query, in document order
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        select(&self.segments, value)
+    }
+}
+
+fn select<'a>(segments: &[Segment], value: &'a Value) -> Vec<&'a Value> {
+    let mut nodes = vec![value];
+    for segment in segments {
+        nodes = nodes
+            .into_iter()
+            .flat_map(|node| apply(segment, node))
+            .collect();
+    }
+    nodes
+}
+
+fn apply<'a>(segment: &Segment, value: &'a Value) -> Vec<&'a Value> {
+    match segment {
+        Segment::Child(name) => value.get(name).into_iter().collect(),
+        Segment::Index(index) => {
+            let Some(items) = value.as_array() else {
+                return Vec::new();
+            };
+            let index = if *index < 0 {
+                items.len() as i64 + index
+            } else {
+                *index
+            };
+            usize::try_from(index)
+                .ok()
+                .and_then(|x| items.get(x))
+                .into_iter()
+                .collect()
+        }
+        Segment::Wildcard => children(value),
+        Segment::Descendants => {
+            let mut nodes = vec![value];
+            let mut pos = 0;
+            while pos < nodes.len() {
+                let node = nodes[pos];
+                nodes.extend(children(node));
+                pos += 1;
+            }
+            nodes
+        }
+        Segment::Filter(filter) => children(value)
+            .into_iter()
+            .filter(|item| filter.matches(item))
+            .collect(),
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        Value::Object(map) => map.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+impl Filter {
+    fn matches(&self, item: &Value) -> bool {
+        let found = select(&self.path, item);
+        match &self.comparison {
+            None => !found.is_empty(),
+            Some((comparison, expected)) => found
+                .iter()
+                .any(|value| compare(value, *comparison, expected)),
+        }
+    }
+}
+
+fn compare(value: &Value, comparison: Comparison, expected: &Value) -> bool {
+    let ordering = match (value, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ if value == expected => Some(Ordering::Equal),
+        _ => None,
+    };
+    match comparison {
+        Comparison::Eq => ordering == Some(Ordering::Equal),
+        Comparison::Ne => ordering != Some(Ordering::Equal),
+        Comparison::Lt => ordering == Some(Ordering::Less),
+        Comparison::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Comparison::Gt => ordering == Some(Ordering::Greater),
+        Comparison::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> anyhow::Result<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        anyhow::anyhow!("{} at position {}", message, self.pos + 1)
+    }
+
+    /// Parses segments until the end, or until the end of the path in a filter
+    fn segments(&mut self, in_filter: bool) -> anyhow::Result<Vec<Segment>> {
+        let mut segments = Vec::new();
+        loop {
+            if in_filter {
+                self.skip_whitespace();
+            }
+            match self.peek() {
+                None => break,
+                Some('.') if self.starts_with("..") => {
+                    self.pos += 2;
+                    segments.push(Segment::Descendants);
+                    if self.peek() == Some('[') {
+                        continue;
+                    }
+                    segments.push(self.name()?);
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    if self.peek() == Some('[') {
+                        continue;
+                    }
+                    segments.push(self.name()?);
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    segments.push(self.bracket()?);
+                }
+                Some(_) if in_filter => break,
+                Some(c) => return Err(self.error(&format!("unexpected '{}'", c))),
+            }
+        }
+        Ok(segments)
+    }
+
+    fn name(&mut self) -> anyhow::Result<Segment> {
+        if self.peek() == Some('*') {
+            self.pos += 1;
+            return Ok(Segment::Wildcard);
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '$')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a name"));
+        }
+        Ok(Segment::Child(self.chars[start..self.pos].iter().collect()))
+    }
+
+    /// Parses what follows a `[`, including the closing `]`
+    fn bracket(&mut self) -> anyhow::Result<Segment> {
+        self.skip_whitespace();
+        let segment = match self.peek() {
+            Some(']') => Segment::Wildcard,
+            Some('*') => {
+                self.pos += 1;
+                Segment::Wildcard
+            }
+            Some('\'') | Some('"') => match self.literal()? {
+                Value::String(name) => Segment::Child(name),
+                _ => unreachable!("quoted literals are strings"),
+            },
+            Some('?') => {
+                self.pos += 1;
+                self.expect('(')?;
+                let filter = self.filter()?;
+                self.expect(')')?;
+                Segment::Filter(filter)
+            }
+            _ => {
+                let start = self.pos;
+                if self.peek() == Some('-') {
+                    self.pos += 1;
+                }
+                while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    self.pos += 1;
+                }
+                let index: String = self.chars[start..self.pos].iter().collect();
+                Segment::Index(
+                    index
+                        .parse()
+                        .map_err(|_| self.error("expected an index, name, '*' or filter"))?,
+                )
+            }
+        };
+        self.expect(']')?;
+        Ok(segment)
+    }
+
+    fn filter(&mut self) -> anyhow::Result<Filter> {
+        self.expect('@')?;
+        let path = self.segments(true)?;
+        self.skip_whitespace();
+        let comparison = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ]
+        .into_iter()
+        .find(|(operator, _)| self.starts_with(operator));
+        let comparison = match comparison {
+            Some((operator, comparison)) => {
+                self.pos += operator.len();
+                self.skip_whitespace();
+                Some((comparison, self.literal()?))
+            }
+            None => None,
+        };
+        Ok(Filter { path, comparison })
+    }
+
+    /// Parses a quoted string, a number, `true`, `false` or `null`
+    fn literal(&mut self) -> anyhow::Result<Value> {
+        match self.peek() {
+            Some(quote @ ('\'' | '"')) => {
+                self.pos += 1;
+                let mut text = String::new();
+                loop {
+                    match self.peek() {
+                        None => return Err(self.error("unterminated string")),
+                        Some('\\') => {
+                            self.pos += 1;
+                            text.extend(self.peek());
+                        }
+                        Some(c) if c == quote => break,
+                        Some(c) => text.push(c),
+                    }
+                    self.pos += 1;
+                }
+                self.pos += 1;
+                Ok(Value::String(text))
+            }
+            _ => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '-' || c == '+' || c == '.')
+                {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                serde_json::from_str(&text)
+                    .map_err(|_| self.error("expected a string, number, true, false or null"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "endpoints": [
+                {"path": "/pets", "method": "get", "tags": ["pets"], "responses": 2},
+                {"path": "/pets", "method": "post", "request": {"name": "Pet"}, "responses": 1},
+                {"path": "/stores/{id}", "method": "delete", "responses": 0}
+            ],
+            "models": {"Pet": {"name": "Pet"}, "Store": {"name": "Store"}}
+        })
+    }
+
+    fn select(query: &str) -> Vec<Value> {
+        let document = document();
+        parse(query)
+            .unwrap()
+            .select(&document)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn root_selects_the_document() {
+        assert_eq!(select("$"), vec![document()]);
+        assert_eq!(select("."), vec![document()]);
+    }
+
+    #[test]
+    fn children_and_indexes() {
+        assert_eq!(select("$.endpoints[0].path"), vec![json!("/pets")]);
+        assert_eq!(
+            select("$['endpoints'][-1][\"path\"]"),
+            vec![json!("/stores/{id}")]
+        );
+        assert_eq!(select(".endpoints[1].method"), vec![json!("post")]);
+        assert!(select("$.endpoints[3]").is_empty());
+        assert!(select("$.missing.path").is_empty());
+    }
+
+    #[test]
+    fn wildcards_and_descendants() {
+        let methods = vec![json!("get"), json!("post"), json!("delete")];
+        assert_eq!(select("$.endpoints[*].method"), methods);
+        assert_eq!(select("$.endpoints.*.method"), methods);
+        assert_eq!(select(".endpoints[].method"), methods);
+        assert_eq!(
+            select("$..name"),
+            vec![json!("Pet"), json!("Store"), json!("Pet")]
+        );
+        assert_eq!(
+            select("$..[0]"),
+            vec![
+                json!({"path": "/pets", "method": "get", "tags": ["pets"], "responses": 2}),
+                json!("pets")
+            ]
+        );
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(
+            select("$.endpoints[?(@.method==\"post\")].path"),
+            vec![json!("/pets")]
+        );
+        assert_eq!(
+            select("$.endpoints[?( @.method != 'get' )].method"),
+            vec![json!("post"), json!("delete")]
+        );
+        assert_eq!(
+            select("$.endpoints[?(@.responses >= 1)].method"),
+            vec![json!("get"), json!("post")]
+        );
+        assert_eq!(
+            select("$.endpoints[?(@.responses < 1)].method"),
+            vec![json!("delete")]
+        );
+        assert_eq!(
+            select("$.endpoints[?(@.request)].method"),
+            vec![json!("post")]
+        );
+        assert_eq!(
+            select("$.endpoints[?(@.tags[0] == 'pets')].method"),
+            vec![json!("get")]
+        );
+    }
+
+    #[test]
+    fn invalid_queries_are_errors() {
+        let error = |query: &str| parse(query).err().unwrap().to_string();
+        assert_eq!(
+            error("endpoints"),
+            "Invalid query 'endpoints', it must start with '$' or '.'"
+        );
+        assert_eq!(
+            error("$.endpoints[0"),
+            "Invalid query '$.endpoints[0': expected ']' at position 13"
+        );
+        assert_eq!(
+            error("$.endpoints[x]"),
+            "Invalid query '$.endpoints[x]': expected an index, name, '*' or filter at position 12"
+        );
+        assert_eq!(
+            error("$.endpoints[?(@.method == 'get)]"),
+            "Invalid query '$.endpoints[?(@.method == 'get)]': unterminated string at position 32"
+        );
+        assert_eq!(
+            error("$.endpoints[?(@.method == get)]"),
+            "Invalid query '$.endpoints[?(@.method == get)]': expected a string, number, true, false or null at position 29"
+        );
+        assert!(parse("$.endpoints.").is_err());
+        assert!(parse("$endpoints").is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
+
+use crate::{cache, serde_method::DataStructure};
+
+/// Holds every object and array model once, keyed by its canonical identity. The objects
+/// nested in a registered model are referenced by their `object_name`, without their properties,
+/// as they are registered as models of their own. The first model registered with an identity is
+/// the one kept, while each flattened list keeps where its models are used
+#[derive(Debug, Default)]
+pub struct ModelRegistry {
+    models: IndexMap<String, DataStructure>,
+}
+
+impl ModelRegistry {
+    /// Registers the model and the objects and arrays nested in it, returning them in depth first
+    /// order without duplicates. The returned models keep their own `is_root`, `name`,
+    /// `wire_name`, `required` and `mapped_type`, whichever model was registered first
+    pub fn flatten(&mut self, model: &DataStructure) -> Vec<DataStructure> {
+        let mut identities = Vec::new();
+        identify(model, &mut identities);
+        let mut flat = Vec::new();
+        self.register(model, &identities, 0, &mut HashSet::new(), &mut flat);
+        flat
+    }
+
+    /// Registers the node at `index` of the identities and its properties, returning the index
+    /// after its subtree
+    fn register(
+        &mut self,
+        node: &DataStructure,
+        identities: &[(String, usize)],
+        index: usize,
+        seen: &mut HashSet<String>,
+        flat: &mut Vec<DataStructure>,
+    ) -> usize {
+        let (identity, size) = &identities[index];
+        if matches!(node.property_type.as_str(), "Object" | "Array") {
+            // The models nested in a model that was already flattened are already in the list
+            if !seen.insert(identity.clone()) {
+                return index + size;
+            }
+            let model = shallow(node);
+            if !self.models.contains_key(identity) {
+                self.models.insert(identity.clone(), model.clone());
+            }
+            flat.push(model);
+        }
+        let mut next = index + 1;
+        for property in &node.properties {
+            next = self.register(property, identities, next, seen, flat);
+        }
+        next
+    }
+
+    /// The registered models in the order they were first registered
+    pub fn into_models(self) -> Vec<DataStructure> {
+        self.models.into_values().collect()
+    }
+}
+
+/// Computes the canonical identity of the node and of everything in it bottom up, hashing each
+/// node once. The identities are pushed in depth first order with the size of their subtree.
+///
+/// The identity is built from everything except where the node is used: its `name` and
+/// `wire_name`, which are the key of the property, `is_root`, `required` and `mapped_type`, which
+/// has the optional layout. Those of the properties are part of the identity of the object they
+/// are in instead, so a model referenced by several properties is registered once.
+fn identify(node: &DataStructure, identities: &mut Vec<(String, usize)>) -> String {
+    let index = identities.len();
+    identities.push(Default::default());
+    let mut parts = vec![
+        format!("{:?}", node.description),
+        format!("{:?}", node.format),
+        format!("{:?}", node.nullable),
+        format!("{:?}", node.required_properties),
+        format!("{:?}", node.property_type),
+        format!("{:?}", node.object_name),
+        format!("{:?}", node.pattern),
+        format!("{:?}", node.min),
+        format!("{:?}", node.max),
+        format!("{:?}", node.min_length),
+        format!("{:?}", node.max_length),
+        format!("{:?}", node.type_override),
+        format!("{:?}", node.extensions),
+    ];
+    for property in &node.properties {
+        parts.push(identify(property, identities));
+        parts.push(format!(
+            "{:?}",
+            (
+                &property.name,
+                &property.wire_name,
+                property.required,
+                &property.mapped_type
+            )
+        ));
+    }
+    let identity = cache::hash(&parts.iter().map(|x| x.as_bytes()).collect::<Vec<_>>());
+    identities[index] = (identity.clone(), identities.len() - index);
+    identity
+}
+
+/// A copy of the model with the objects nested in it referenced by name
+fn shallow(model: &DataStructure) -> DataStructure {
+    DataStructure {
+        properties: model.properties.iter().map(reference).collect(),
+        ..without_properties(model)
+    }
+}
+
+fn reference(property: &DataStructure) -> DataStructure {
+    match property.property_type.as_str() {
+        "Object" if property.object_name.is_some() => without_properties(property),
+        "Array" => shallow(property),
+        _ => property.clone(),
+    }
+}
+
+/// Copies the node without cloning its properties
+fn without_properties(node: &DataStructure) -> DataStructure {
+    // Destructured so that a new field is not forgotten
+    let DataStructure {
+        name,
+        description,
+        format,
+        required,
+        nullable,
+        wire_name,
+        properties: _,
+        required_properties,
+        property_type,
+        object_name,
+        is_root,
+        pattern,
+        min,
+        max,
+        min_length,
+        max_length,
+        type_override,
+        mapped_type,
+        extensions,
+    } = node;
+    DataStructure {
+        name: name.clone(),
+        description: description.clone(),
+        format: format.clone(),
+        required: *required,
+        nullable: *nullable,
+        wire_name: wire_name.clone(),
+        properties: Vec::new(),
+        required_properties: required_properties.clone(),
+        property_type: property_type.clone(),
+        object_name: object_name.clone(),
+        is_root: *is_root,
+        pattern: pattern.clone(),
+        min: min.clone(),
+        max: max.clone(),
+        min_length: *min_length,
+        max_length: *max_length,
+        type_override: type_override.clone(),
+        mapped_type: mapped_type.clone(),
+        extensions: extensions.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, property_type: &str) -> DataStructure {
+        DataStructure {
+            name: name.to_string(),
+            property_type: property_type.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn object(name: &str, properties: Vec<DataStructure>) -> DataStructure {
+        DataStructure {
+            object_name: Some(name.to_string()),
+            properties,
+            ..field(name, "Object")
+        }
+    }
+
+    fn address() -> DataStructure {
+        object(
+            "Address",
+            vec![field("street", "String"), field("city", "String")],
+        )
+    }
+
+    fn names(models: &[DataStructure]) -> Vec<&str> {
+        models
+            .iter()
+            .map(|x| x.object_name.as_deref().unwrap_or(""))
+            .collect()
+    }
+
+    #[test]
+    fn identical_models_are_registered_once() {
+        let mut registry = ModelRegistry::default();
+        let order = object("Order", vec![address(), field("id", "Integer")]);
+        let flat = registry.flatten(&order);
+        let flat_again = registry.flatten(&address());
+        assert_eq!(names(&flat), ["Order", "Address"]);
+        assert_eq!(names(&flat_again), ["Address"]);
+        assert_eq!(names(&registry.into_models()), ["Order", "Address"]);
+    }
+
+    #[test]
+    fn nested_models_are_referenced_by_name() {
+        let mut registry = ModelRegistry::default();
+        let mut lines = field("lines", "Array");
+        lines.properties = vec![address()];
+        let order = object("Order", vec![address(), lines]);
+        let models = registry.flatten(&order);
+        assert_eq!(names(&models), ["Order", "Address", ""]);
+        let order = &models[0];
+        assert!(order.properties[0].properties.is_empty());
+        assert!(order.properties[1].properties[0].properties.is_empty());
+        assert_eq!(models[1].properties.len(), 2);
+    }
+
+    #[test]
+    fn models_are_flattened_once_per_endpoint() {
+        let mut registry = ModelRegistry::default();
+        let order = object("Order", vec![address(), address()]);
+        assert_eq!(names(&registry.flatten(&order)), ["Order", "Address"]);
+    }
+
+    #[test]
+    fn type_override_wire_name_and_mapped_type_are_part_of_the_identity() {
+        let mut overridden = address();
+        overridden.properties[0].type_override = Some("Street".to_string());
+        let mut renamed = address();
+        renamed.properties[0].wire_name = Some("street_name".to_string());
+        let mut mapped = address();
+        mapped.properties[0].mapped_type = Some("String?".to_string());
+        let mut registry = ModelRegistry::default();
+        for model in [address(), overridden, renamed, mapped] {
+            registry.flatten(&model);
+        }
+        assert_eq!(registry.into_models().len(), 4);
+    }
+
+    #[test]
+    fn where_the_model_is_used_is_not_part_of_the_identity() {
+        let mut root = address();
+        root.is_root = true;
+        root.mapped_type = Some("Address".to_string());
+        let mut nested = address();
+        nested.required = true;
+        nested.mapped_type = Some("Address?".to_string());
+        let mut registry = ModelRegistry::default();
+        registry.flatten(&root);
+        registry.flatten(&nested);
+        assert_eq!(registry.into_models().len(), 1);
+    }
+
+    #[test]
+    fn a_root_model_nested_in_another_endpoint_keeps_where_it_is_used() {
+        let mut nested = address();
+        nested.name = "billing".to_string();
+        nested.required = true;
+        let mut order = object("Order", vec![nested]);
+        order.is_root = true;
+        let mut root = address();
+        root.is_root = true;
+        let mut registry = ModelRegistry::default();
+        let orders = registry.flatten(&order);
+        let addresses = registry.flatten(&root);
+        assert_eq!(names(&registry.into_models()), ["Order", "Address"]);
+        assert_eq!(orders[1].name, "billing");
+        assert!(orders[1].required && !orders[1].is_root);
+        assert_eq!(addresses[0].name, "Address");
+        assert!(!addresses[0].required && addresses[0].is_root);
+    }
+
+    #[test]
+    fn a_model_used_by_several_properties_is_registered_once() {
+        let mut billing = address();
+        billing.name = "billing".to_string();
+        billing.wire_name = Some("billing_address".to_string());
+        let mut shipping = address();
+        shipping.name = "shipping".to_string();
+        let order = object("Order", vec![billing, shipping]);
+        let mut registry = ModelRegistry::default();
+        let flat = registry.flatten(&order);
+        assert_eq!(names(&flat), ["Order", "Address"]);
+        let order = &flat[0];
+        assert_eq!(order.properties[0].name, "billing");
+        assert_eq!(order.properties[1].name, "shipping");
+    }
+
+    #[test]
+    fn identities_are_computed_once_per_node() {
+        let order = object("Order", vec![address(), field("id", "Integer")]);
+        let mut identities = Vec::new();
+        let identity = identify(&order, &mut identities);
+        let sizes: Vec<usize> = identities.iter().map(|(_, size)| *size).collect();
+        assert_eq!(identities[0].0, identity);
+        assert_eq!(sizes, [5, 3, 1, 1, 1]);
+    }
+}
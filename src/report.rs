@@ -0,0 +1,318 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+use simplelog::{error, warn};
+
+use crate::{
+    lint::{Diagnostic, Severity, RULES},
+    source_location::locate_yaml,
+    ReportFormat,
+};
+
+/// Code of the errors found by `OApi::check`, which are reported with the lint diagnostics but
+/// are not a lint rule, so they cannot be turned off as nothing is generated from such a spec
+pub const OPENAPI_INVALID: &str = "openapi-invalid";
+
+/// Where a diagnostic is in the original spec files, before they are merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: PathBuf,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column
+    pub column: usize,
+}
+
+/// Writes the diagnostics in the format to the file, or to stdout if there is none.
+/// The text format is logged instead
+pub fn write(
+    diagnostics: &[Diagnostic],
+    format: ReportFormat,
+    files: &[PathBuf],
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    let located: Vec<(&Diagnostic, Option<Location>)> = diagnostics
+        .iter()
+        .map(|x| (x, locate_in_files(files, &x.pointer)))
+        .collect();
+    let report = match format {
+        ReportFormat::Text => {
+            for (diagnostic, location) in &located {
+                log_text(diagnostic, location.as_ref());
+            }
+            return Ok(());
+        }
+        ReportFormat::Json => {
+            let mut lines = String::new();
+            for (diagnostic, location) in &located {
+                lines.push_str(&serde_json::to_string(&json_line(
+                    diagnostic,
+                    location.as_ref(),
+                ))?);
+                lines.push('\n');
+            }
+            lines
+        }
+        ReportFormat::Sarif => {
+            let mut sarif = serde_json::to_string_pretty(&sarif(&located))?;
+            sarif.push('\n');
+            sarif
+        }
+    };
+    match output {
+        Some(file) => std::fs::write(file, report)?,
+        None => print!("{}", report),
+    }
+    Ok(())
+}
+
+fn log_text(diagnostic: &Diagnostic, location: Option<&Location>) {
+    let place = match location {
+        Some(location) => format!(
+            "{}:{}:{} ({})",
+            location.file.display(),
+            location.line,
+            location.column,
+            diagnostic.pointer
+        ),
+        None => diagnostic.pointer.clone(),
+    };
+    // The pointer of the whole document is empty
+    let message = if place.is_empty() {
+        diagnostic.message.clone()
+    } else {
+        format!("{}: {}", place, diagnostic.message)
+    };
+    match diagnostic.severity {
+        Severity::Error => error!("[{}] {}", diagnostic.rule, message),
+        Severity::Warning => warn!("[{}] {}", diagnostic.rule, message),
+    }
+}
+
+fn json_line(diagnostic: &Diagnostic, location: Option<&Location>) -> serde_json::Value {
+    json!({
+        "rule": diagnostic.rule,
+        "severity": diagnostic.severity.to_string(),
+        "message": diagnostic.message,
+        "file": location.map(|x| x.file.to_string_lossy().replace('\\', "/")),
+        "line": location.map(|x| x.line),
+        "column": location.map(|x| x.column),
+        "pointer": diagnostic.pointer,
+    })
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+    }
+}
+
+/// The ID, description and default severity of the lint rules and `openapi-invalid`, which a
+/// diagnostic can be reported for, in the order of the SARIF `rules`
+fn sarif_rules() -> impl Iterator<Item = (&'static str, &'static str, Severity)> {
+    RULES
+        .iter()
+        .map(|rule| (rule.id, rule.description, rule.severity))
+        .chain([(
+            OPENAPI_INVALID,
+            "Spec is not a valid OpenAPI 3 document",
+            Severity::Error,
+        )])
+}
+
+/// The diagnostics as a SARIF 2.1.0 log
+fn sarif(located: &[(&Diagnostic, Option<Location>)]) -> serde_json::Value {
+    let rules: Vec<serde_json::Value> = sarif_rules()
+        .map(|(id, description, severity)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+                "defaultConfiguration": { "level": sarif_level(severity) },
+            })
+        })
+        .collect();
+    let results: Vec<serde_json::Value> = located
+        .iter()
+        .map(|(diagnostic, location)| {
+            let mut result = json!({
+                "ruleId": diagnostic.rule,
+                "ruleIndex": sarif_rules().position(|(id, _, _)| id == diagnostic.rule),
+                "level": sarif_level(diagnostic.severity),
+                "message": { "text": diagnostic.message },
+                "properties": { "pointer": diagnostic.pointer },
+            });
+            if let Some(location) = location {
+                result["locations"] = json!([{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": location.file.to_string_lossy().replace('\\', "/"),
+                        },
+                        "region": {
+                            "startLine": location.line,
+                            "startColumn": location.column,
+                        },
+                    },
+                    "logicalLocations": [{
+                        "fullyQualifiedName": diagnostic.pointer,
+                        "kind": "member",
+                    }],
+                }]);
+            }
+            result
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+/// Finds the pointer in the file that defines the most of it, as the spec is merged from the files
+fn locate_in_files(files: &[PathBuf], pointer: &str) -> Option<Location> {
+    let tokens: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let mut best: Option<(usize, Location)> = None;
+    for file in files {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        if let Some(position) = locate_yaml(&content, &tokens) {
+            if best.as_ref().is_none_or(|(x, _)| position.depth > *x) {
+                let location = Location {
+                    file: file.clone(),
+                    line: position.line,
+                    column: position.column,
+                };
+                best = Some((position.depth, location));
+            }
+        }
+    }
+    best.map(|(_, location)| location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes the files to a folder unique to the test
+    fn files(name: &str, contents: &[(&str, &str)]) -> Vec<PathBuf> {
+        let dir =
+            std::env::temp_dir().join(format!("api-gen-report-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        contents
+            .iter()
+            .map(|(file, content)| {
+                let path = dir.join(file);
+                std::fs::write(&path, content).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    fn at(file: &Path, line: usize, column: usize) -> Option<Location> {
+        Some(Location {
+            file: file.to_path_buf(),
+            line,
+            column,
+        })
+    }
+
+    #[test]
+    fn escaped_tokens_and_sequence_items_are_found() {
+        let files = files(
+            "escaped",
+            &[(
+                "api.yaml",
+                "paths:\n  /stores/{id}:\n    get:\n      parameters:\n        - name: id\n        - name: q\n",
+            )],
+        );
+        assert_eq!(
+            locate_in_files(&files, "/paths/~1stores~1{id}/get"),
+            at(&files[0], 3, 5)
+        );
+        assert_eq!(
+            locate_in_files(&files, "/paths/~1stores~1{id}/get/parameters/1/name"),
+            at(&files[0], 6, 11)
+        );
+    }
+
+    #[test]
+    fn block_scalars_and_flow_mappings_are_parsed() {
+        let files = files(
+            "styles",
+            &[(
+                "api.yaml",
+                "info:\n  description: |\n    paths:\n      /fake: {}\npaths:\n  /pets: { get: { responses: {} } }\n",
+            )],
+        );
+        assert_eq!(
+            locate_in_files(&files, "/paths/~1pets/get/responses"),
+            at(&files[0], 6, 19)
+        );
+    }
+
+    #[test]
+    fn the_file_defining_the_most_of_the_pointer_is_used() {
+        let files = files(
+            "merged",
+            &[
+                (
+                    "api.yaml",
+                    "components:\n  schemas:\n    Pet:\n      type: object\n",
+                ),
+                (
+                    "shared_models.yaml",
+                    "components:\n  schemas:\n    Address:\n      type: object\n",
+                ),
+            ],
+        );
+        assert_eq!(
+            locate_in_files(&files, "/components/schemas/Address/type"),
+            at(&files[1], 4, 7)
+        );
+        assert_eq!(
+            locate_in_files(&files, "/components/schemas/Pet"),
+            at(&files[0], 3, 5)
+        );
+    }
+
+    #[test]
+    fn the_whole_document_and_missing_files_have_no_location() {
+        let mut files = files("missing", &[("api.yaml", "openapi: 3.0.0\n")]);
+        assert_eq!(locate_in_files(&files, ""), None);
+        assert_eq!(locate_in_files(&files, "/paths"), None);
+        files[0].set_file_name("other.yaml");
+        assert_eq!(locate_in_files(&files, "/openapi"), None);
+    }
+
+    #[test]
+    fn an_invalid_spec_is_reported_after_the_lint_rules() {
+        let diagnostic = Diagnostic {
+            rule: OPENAPI_INVALID,
+            severity: Severity::Error,
+            pointer: String::new(),
+            message: "Parameter `{id}` is not present in path".to_string(),
+        };
+        let log = sarif(&[(&diagnostic, None)]);
+        let driver = &log["runs"][0]["tool"]["driver"];
+        assert_eq!(driver["rules"][RULES.len()]["id"], OPENAPI_INVALID);
+        assert_eq!(log["runs"][0]["results"][0]["ruleIndex"], RULES.len());
+        assert!(RULES.iter().all(|rule| rule.id != OPENAPI_INVALID));
+    }
+}
@@ -0,0 +1,189 @@
+use std::borrow::Borrow;
+
+use indexmap::IndexMap;
+use openapiv3::{
+    AdditionalProperties, MediaType, OpenAPI, Parameter, ParameterSchemaOrContent, ReferenceOr,
+    RequestBody, Response, Schema, SchemaKind, Type,
+};
+
+/// A schema found while walking the OpenAPI document
+pub enum SchemaNode<'a> {
+    Item(&'a Schema),
+    /// A `$ref` to another schema, which is walked where it is defined
+    Reference,
+}
+
+/// Calls `visit` with the JSON pointer and the schema of every schema in the document,
+/// including the ones nested in properties, items and compositions
+pub fn walk_schemas<'a>(doc: &'a OpenAPI, visit: &mut dyn FnMut(&str, SchemaNode<'a>)) {
+    if let Some(components) = &doc.components {
+        for (name, schema) in &components.schemas {
+            walk(
+                schema,
+                &format!("/components/schemas/{}", escape(name)),
+                visit,
+            );
+        }
+        for (name, parameter) in &components.parameters {
+            let path = format!("/components/parameters/{}", escape(name));
+            walk_parameter(parameter, &path, visit);
+        }
+        for (name, body) in &components.request_bodies {
+            let path = format!("/components/requestBodies/{}", escape(name));
+            walk_request_body(body, &path, visit);
+        }
+        for (name, response) in &components.responses {
+            let path = format!("/components/responses/{}", escape(name));
+            walk_response(response, &path, visit);
+        }
+    }
+
+    for (path, item) in doc.paths.iter() {
+        let item = match item.as_item() {
+            Some(item) => item,
+            None => continue,
+        };
+        let path = format!("/paths/{}", escape(path));
+        for (i, parameter) in item.parameters.iter().enumerate() {
+            walk_parameter(parameter, &format!("{}/parameters/{}", path, i), visit);
+        }
+        for (method, operation) in item.iter() {
+            let path = format!("{}/{}", path, method);
+            for (i, parameter) in operation.parameters.iter().enumerate() {
+                walk_parameter(parameter, &format!("{}/parameters/{}", path, i), visit);
+            }
+            if let Some(body) = &operation.request_body {
+                walk_request_body(body, &format!("{}/requestBody", path), visit);
+            }
+            if let Some(response) = &operation.responses.default {
+                walk_response(response, &format!("{}/responses/default", path), visit);
+            }
+            for (status, response) in &operation.responses.responses {
+                walk_response(response, &format!("{}/responses/{}", path, status), visit);
+            }
+        }
+    }
+}
+
+/// Escapes a JSON pointer token, e.g. `/pets/{id}` into `~1pets~1{id}`
+pub fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn walk<'a, T: Borrow<Schema>>(
+    schema: &'a ReferenceOr<T>,
+    path: &str,
+    visit: &mut dyn FnMut(&str, SchemaNode<'a>),
+) {
+    match schema {
+        ReferenceOr::Reference { .. } => visit(path, SchemaNode::Reference),
+        ReferenceOr::Item(schema) => walk_schema(schema.borrow(), path, visit),
+    }
+}
+
+fn walk_schema<'a>(schema: &'a Schema, path: &str, visit: &mut dyn FnMut(&str, SchemaNode<'a>)) {
+    visit(path, SchemaNode::Item(schema));
+    match &schema.schema_kind {
+        SchemaKind::Type(Type::Object(object)) => {
+            for (name, property) in &object.properties {
+                walk(
+                    property,
+                    &format!("{}/properties/{}", path, escape(name)),
+                    visit,
+                );
+            }
+            if let Some(AdditionalProperties::Schema(schema)) = &object.additional_properties {
+                walk(
+                    schema.as_ref(),
+                    &format!("{}/additionalProperties", path),
+                    visit,
+                );
+            }
+        }
+        SchemaKind::Type(Type::Array(array)) => {
+            if let Some(items) = &array.items {
+                walk(items, &format!("{}/items", path), visit);
+            }
+        }
+        SchemaKind::Type(_) => {}
+        SchemaKind::OneOf { one_of } => walk_all(one_of, &format!("{}/oneOf", path), visit),
+        SchemaKind::AllOf { all_of } => walk_all(all_of, &format!("{}/allOf", path), visit),
+        SchemaKind::AnyOf { any_of } => walk_all(any_of, &format!("{}/anyOf", path), visit),
+        SchemaKind::Not { not } => walk(not.as_ref(), &format!("{}/not", path), visit),
+        SchemaKind::Any(any) => {
+            for (name, property) in &any.properties {
+                walk(
+                    property,
+                    &format!("{}/properties/{}", path, escape(name)),
+                    visit,
+                );
+            }
+            if let Some(items) = &any.items {
+                walk(items, &format!("{}/items", path), visit);
+            }
+        }
+    }
+}
+
+fn walk_all<'a>(
+    schemas: &'a [ReferenceOr<Schema>],
+    path: &str,
+    visit: &mut dyn FnMut(&str, SchemaNode<'a>),
+) {
+    for (i, schema) in schemas.iter().enumerate() {
+        walk(schema, &format!("{}/{}", path, i), visit);
+    }
+}
+
+fn walk_content<'a>(
+    content: &'a IndexMap<String, MediaType>,
+    path: &str,
+    visit: &mut dyn FnMut(&str, SchemaNode<'a>),
+) {
+    for (media_type, media) in content {
+        if let Some(schema) = &media.schema {
+            let path = format!("{}/content/{}/schema", path, escape(media_type));
+            walk(schema, &path, visit);
+        }
+    }
+}
+
+fn walk_parameter<'a>(
+    parameter: &'a ReferenceOr<Parameter>,
+    path: &str,
+    visit: &mut dyn FnMut(&str, SchemaNode<'a>),
+) {
+    let parameter_data = match parameter.as_item() {
+        Some(Parameter::Query { parameter_data, .. })
+        | Some(Parameter::Header { parameter_data, .. })
+        | Some(Parameter::Path { parameter_data, .. })
+        | Some(Parameter::Cookie { parameter_data, .. }) => parameter_data,
+        None => return,
+    };
+    match &parameter_data.format {
+        ParameterSchemaOrContent::Schema(schema) => {
+            walk(schema, &format!("{}/schema", path), visit)
+        }
+        ParameterSchemaOrContent::Content(content) => walk_content(content, path, visit),
+    }
+}
+
+fn walk_request_body<'a>(
+    body: &'a ReferenceOr<RequestBody>,
+    path: &str,
+    visit: &mut dyn FnMut(&str, SchemaNode<'a>),
+) {
+    if let Some(body) = body.as_item() {
+        walk_content(&body.content, path, visit);
+    }
+}
+
+fn walk_response<'a>(
+    response: &'a ReferenceOr<Response>,
+    path: &str,
+    visit: &mut dyn FnMut(&str, SchemaNode<'a>),
+) {
+    if let Some(response) = response.as_item() {
+        walk_content(&response.content, path, visit);
+    }
+}
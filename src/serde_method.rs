@@ -1,9 +1,22 @@
+use std::collections::BTreeMap;
+
 use indexmap::IndexMap;
-use openapiv3::{ReferenceOr, Schema, StatusCode};
+use openapiv3::{
+    IntegerFormat, NumberFormat, ReferenceOr, Schema, StatusCode, StringFormat,
+    VariantOrUnknownOrEmpty,
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use simplelog::{debug, info};
 
-use crate::{Endpoint, EndpointExtracted, TemplateData};
+use crate::{
+    config::{Config, TypeOverrides},
+    tera_extensions::{mapped_type, optional_layout},
+    Endpoint, EndpointExtracted, TemplateData,
+};
+
+/// Vendor extension on a schema that sets the name of its model
+const CLASS_NAME_EXTENSION: &str = "x-class-name";
 
 pub fn serde_openapi(contents: String) -> anyhow::Result<TemplateData> {
     let doc: openapiv3::OpenAPI = serde_yaml::from_str(&contents)?;
@@ -36,6 +49,7 @@ pub fn serde_openapi(contents: String) -> anyhow::Result<TemplateData> {
                     })
                     .clone(),
                 description: data.description.clone(),
+                extensions: data.extensions.clone().into_iter().collect(),
             });
         }
     }
@@ -65,7 +79,7 @@ pub fn serde_openapi(contents: String) -> anyhow::Result<TemplateData> {
             request.process_data();
             request.is_root = true;
             if request.property_type == "Object" {
-                request.object_name = Some(request.name.clone());
+                request.object_name = Some(class_name(&request).unwrap_or(request.name.clone()));
             }
             debug!("Request: {:#?}", request);
             new_endpoint.request = Some(request);
@@ -81,7 +95,8 @@ pub fn serde_openapi(contents: String) -> anyhow::Result<TemplateData> {
                 response.process_data();
                 response.is_root = true;
                 if response.property_type == "Object" {
-                    response.object_name = Some(response.name.clone());
+                    response.object_name =
+                        Some(class_name(&response).unwrap_or(response.name.clone()));
                 }
                 debug!("Response: {:#?}", response);
                 new_endpoint.response = Some(response);
@@ -100,10 +115,7 @@ fn extract_model(
 ) -> DataStructure {
     match schema {
         ReferenceOr::Reference { reference } => {
-            debug!("Reference: {}", reference);
-            let name = reference.split('/').last().unwrap();
-            let reference_schema = component_schemas.get(name).unwrap();
-            extract_model(reference_schema, component_schemas, name, is_array)
+            extract_reference(reference, component_schemas, is_array)
         }
         ReferenceOr::Item(schema) => {
             extract_model_from_schema(schema, component_schemas, name, is_array)
@@ -111,28 +123,74 @@ fn extract_model(
     }
 }
 
+/// Extracts the component schema, where the model of an object is named after the component
+/// wherever it is used
+fn extract_reference(
+    reference: &str,
+    component_schemas: &IndexMap<String, ReferenceOr<Schema>>,
+    is_array: bool,
+) -> DataStructure {
+    debug!("Reference: {}", reference);
+    let name = reference.split('/').next_back().unwrap();
+    let reference_schema = component_schemas.get(name).unwrap();
+    let mut model = extract_model(reference_schema, component_schemas, name, is_array);
+    if model.object_name.is_some() && class_name(&model).is_none() {
+        model.object_name = Some(name.to_string());
+    }
+    model
+}
+
+/// The name of a string format as used in the `types` of the config
+pub fn string_format(format: &VariantOrUnknownOrEmpty<StringFormat>) -> Option<String> {
+    match format {
+        VariantOrUnknownOrEmpty::Item(item) => Some(match item {
+            StringFormat::Date => "Date".to_string(),
+            StringFormat::DateTime => "DateTime".to_string(),
+            StringFormat::Password => "Password".to_string(),
+            StringFormat::Byte => "Byte".to_string(),
+            StringFormat::Binary => "Binary".to_string(),
+        }),
+        VariantOrUnknownOrEmpty::Unknown(format) => Some(format.to_string()),
+        VariantOrUnknownOrEmpty::Empty => None,
+    }
+}
+
+/// The name of a number format as used in the `types` of the config
+pub fn number_format(format: &VariantOrUnknownOrEmpty<NumberFormat>) -> Option<String> {
+    match format {
+        VariantOrUnknownOrEmpty::Item(item) => Some(match item {
+            NumberFormat::Float => "Float".to_string(),
+            NumberFormat::Double => "Double".to_string(),
+        }),
+        VariantOrUnknownOrEmpty::Unknown(format) => Some(format.to_string()),
+        VariantOrUnknownOrEmpty::Empty => None,
+    }
+}
+
+/// The name of an integer format as used in the `types` of the config
+pub fn integer_format(format: &VariantOrUnknownOrEmpty<IntegerFormat>) -> Option<String> {
+    match format {
+        VariantOrUnknownOrEmpty::Item(item) => Some(match item {
+            IntegerFormat::Int32 => "Int32".to_string(),
+            IntegerFormat::Int64 => "Int64".to_string(),
+        }),
+        VariantOrUnknownOrEmpty::Unknown(format) => Some(format.to_string()),
+        VariantOrUnknownOrEmpty::Empty => None,
+    }
+}
+
 fn extract_model_from_schema(
     schema: &Schema,
     component_schemas: &IndexMap<String, ReferenceOr<Schema>>,
     name: &str,
     is_array: bool,
 ) -> DataStructure {
-    match &schema.schema_kind {
+    let mut structure = match &schema.schema_kind {
         openapiv3::SchemaKind::Type(t) => match t {
             openapiv3::Type::String(str) => DataStructure {
                 name: name.to_string(),
                 description: schema.schema_data.description.clone(),
-                format: match &str.format {
-                    openapiv3::VariantOrUnknownOrEmpty::Item(item) => Some(match item {
-                        openapiv3::StringFormat::Date => "Date".to_string(),
-                        openapiv3::StringFormat::DateTime => "DateTime".to_string(),
-                        openapiv3::StringFormat::Password => "Password".to_string(),
-                        openapiv3::StringFormat::Byte => "Byte".to_string(),
-                        openapiv3::StringFormat::Binary => "Binary".to_string(),
-                    }),
-                    openapiv3::VariantOrUnknownOrEmpty::Unknown(format) => Some(format.to_string()),
-                    openapiv3::VariantOrUnknownOrEmpty::Empty => None,
-                },
+                format: string_format(&str.format),
                 required: false,
                 properties: Vec::new(),
                 required_properties: Vec::new(),
@@ -147,14 +205,7 @@ fn extract_model_from_schema(
             openapiv3::Type::Number(num) => DataStructure {
                 name: name.to_string(),
                 description: schema.schema_data.description.clone(),
-                format: match &num.format {
-                    openapiv3::VariantOrUnknownOrEmpty::Item(item) => Some(match item {
-                        openapiv3::NumberFormat::Float => "Float".to_string(),
-                        openapiv3::NumberFormat::Double => "Double".to_string(),
-                    }),
-                    openapiv3::VariantOrUnknownOrEmpty::Unknown(format) => Some(format.to_string()),
-                    openapiv3::VariantOrUnknownOrEmpty::Empty => None,
-                },
+                format: number_format(&num.format),
                 required: false,
                 properties: Vec::new(),
                 required_properties: Vec::new(),
@@ -169,14 +220,7 @@ fn extract_model_from_schema(
             openapiv3::Type::Integer(int) => DataStructure {
                 name: name.to_string(),
                 description: schema.schema_data.description.clone(),
-                format: match &int.format {
-                    openapiv3::VariantOrUnknownOrEmpty::Item(item) => Some(match item {
-                        openapiv3::IntegerFormat::Int32 => "Int32".to_string(),
-                        openapiv3::IntegerFormat::Int64 => "Int64".to_string(),
-                    }),
-                    openapiv3::VariantOrUnknownOrEmpty::Unknown(format) => Some(format.to_string()),
-                    openapiv3::VariantOrUnknownOrEmpty::Empty => None,
-                },
+                format: integer_format(&int.format),
                 required: false,
                 properties: Vec::new(),
                 required_properties: Vec::new(),
@@ -207,21 +251,17 @@ fn extract_model_from_schema(
                     ..Default::default()
                 };
                 for (name, schema) in &obj.properties {
-                    match schema {
+                    let mut property = match schema {
                         ReferenceOr::Reference { reference } => {
-                            let name = reference.split('/').last().unwrap();
-                            let reference_schema = component_schemas.get(name).unwrap();
-                            extract_model(reference_schema, component_schemas, name, false);
+                            extract_reference(reference, component_schemas, false)
                         }
                         ReferenceOr::Item(item) => {
-                            response.properties.push(extract_model_from_schema(
-                                item.as_ref(),
-                                component_schemas,
-                                name,
-                                false,
-                            ));
+                            extract_model_from_schema(item.as_ref(), component_schemas, name, false)
                         }
-                    }
+                    };
+                    property.name = name.clone();
+                    property.wire_name = Some(name.clone());
+                    response.properties.push(property);
                 }
                 response
             }
@@ -244,9 +284,7 @@ fn extract_model_from_schema(
 
                 array.properties.push(match arr.items.as_ref().unwrap() {
                     ReferenceOr::Reference { reference } => {
-                        let name = reference.split('/').last().unwrap();
-                        let reference_schema = component_schemas.get(name).unwrap();
-                        extract_model(reference_schema, component_schemas, name, false)
+                        extract_reference(reference, component_schemas, false)
                     }
                     ReferenceOr::Item(item) => {
                         debug!("Array: {:#?}", item);
@@ -279,28 +317,71 @@ fn extract_model_from_schema(
         openapiv3::SchemaKind::AnyOf { any_of: _ } => todo!("extract any of"),
         openapiv3::SchemaKind::Not { not: _ } => todo!("extract not"),
         openapiv3::SchemaKind::Any(_any) => todo!("extract any"),
+    };
+    structure.extensions = schema.schema_data.extensions.clone().into_iter().collect();
+    structure.nullable = schema.schema_data.nullable;
+    if structure.object_name.is_some() {
+        if let Some(class_name) = class_name(&structure) {
+            structure.object_name = Some(class_name);
+        }
     }
+    structure
 }
 
-#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq, Deserialize)]
+/// The model name set by the `x-class-name` vendor extension of the schema
+fn class_name(structure: &DataStructure) -> Option<String> {
+    structure
+        .extensions
+        .get(CLASS_NAME_EXTENSION)?
+        .as_str()
+        .map(String::from)
+}
+
+/// A model, property or array item
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq, Deserialize, JsonSchema)]
 pub struct DataStructure {
+    /// Name of the property, or of the model for the root of a request or response
     pub name: String,
     pub description: Option<String>,
+    /// Format as named in the `types` of the config, e.g. `DateTime` or `Int64`, or a custom
+    /// format as it is in the spec
     pub format: Option<String>,
+    /// Whether the property is in the `required` list of the object it is in
     pub required: bool,
+    pub nullable: bool,
+    /// The key of the property in the JSON, kept as is for serializers
+    pub wire_name: Option<String>,
+    /// The properties of an object, or the item of an array
     pub properties: Vec<DataStructure>,
+    /// Names of the required properties of an object
     pub required_properties: Vec<String>,
+    /// `Object`, `Array`, `String`, `Number`, `Integer` or `Boolean`
     pub property_type: String,
+    /// Name of the model of an object
     pub object_name: Option<String>,
+    /// Whether it is the model of a request or response body
     pub is_root: bool,
+    /// Regex a string must match
     pub pattern: Option<String>,
+    /// Minimum of a number or integer
     pub min: Option<Int64FloatOrUsize>,
+    /// Maximum of a number or integer
     pub max: Option<Int64FloatOrUsize>,
+    /// Minimum length of a string, or `minItems` of an array
     pub min_length: Option<usize>,
+    /// Maximum length of a string, or `maxItems` of an array
     pub max_length: Option<usize>,
+    /// Type from the config `overrides` used instead of mapping the type
+    pub type_override: Option<String>,
+    /// The type mapped with the config, the same as `map_type` returns, e.g. `List<Order>` or `int`
+    pub mapped_type: Option<String>,
+    /// The `x-` vendor extensions of the schema
+    pub extensions: BTreeMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A numeric limit, `Int` for an integer schema and `Float` for a number schema. `UInt` is only
+/// the default
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub enum Int64FloatOrUsize {
     Int(i64),
     UInt(usize),
@@ -345,6 +426,53 @@ impl From<f64> for Int64FloatOrUsize {
 }
 
 impl DataStructure {
+    /// Sets the type override of this property and its properties, where `models` are the names
+    /// of the model this property is in
+    pub fn apply_type_overrides(&mut self, overrides: &TypeOverrides, models: &[&str]) {
+        if !self.is_root {
+            self.type_override = overrides.find(&self.name, models).map(String::from);
+        }
+        // Owned as the properties are borrowed mutably
+        let names: Vec<String> = self
+            .model_names(models)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        for property in &mut self.properties {
+            property.apply_type_overrides(overrides, &names);
+        }
+    }
+
+    /// The names of the model the properties of this structure are in, where `models` are the
+    /// names of the model this structure is in
+    pub fn model_names<'a>(&'a self, models: &[&'a str]) -> Vec<&'a str> {
+        if self.property_type == "Object" {
+            std::iter::once(self.name.as_str())
+                .chain(self.object_name.as_deref())
+                .collect()
+        } else {
+            models.to_vec()
+        }
+    }
+
+    /// Sets the mapped type of this structure and its properties, where array items are mapped
+    /// without the optional or nullable layout
+    pub fn apply_mapped_types(&mut self, config: &Config, is_item: bool) {
+        let mapped = mapped_type(self, config).map(|mapped| {
+            if is_item {
+                mapped
+            } else {
+                optional_layout(self, config, mapped)
+            }
+        });
+        self.mapped_type = mapped;
+        let items = self.property_type == "Array";
+        for property in &mut self.properties {
+            property.apply_mapped_types(config, items);
+        }
+    }
+
     fn process_data(&mut self) {
         if self.property_type == "Array" {
             debug!("Array: {:#?}", self);
@@ -0,0 +1,247 @@
+use yaml_rust2::{
+    parser::{Event, Parser},
+    scanner::Marker,
+};
+
+/// Where the deepest part of a key path that was found is in a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Number of path segments found
+    pub depth: usize,
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column
+    pub column: usize,
+}
+
+/// A YAML node with where its keys and items start
+enum Node {
+    Mapping(Vec<(Option<String>, Marker, Node)>),
+    Sequence(Vec<(Marker, Node)>),
+    Other,
+}
+
+/// Finds the deepest part of the key path in a YAML or JSON document. Segments of a
+/// sequence are the index of the item
+pub fn locate_yaml<S: AsRef<str>>(content: &str, path: &[S]) -> Option<Position> {
+    let mut parser = Parser::new_from_str(content);
+    let root = loop {
+        match parser.next_token().ok()? {
+            (Event::StreamEnd, _) => return None,
+            (Event::MappingStart(..), _) => break read_mapping(&mut parser)?,
+            (Event::SequenceStart(..), _) => break read_sequence(&mut parser)?,
+            _ => {}
+        }
+    };
+    let mut node = &root;
+    let mut found = None;
+    for (depth, segment) in path.iter().enumerate() {
+        let segment = segment.as_ref();
+        let child = match node {
+            Node::Mapping(entries) => entries
+                .iter()
+                .find(|(key, _, _)| key.as_deref() == Some(segment))
+                .map(|(_, marker, child)| (marker, child)),
+            Node::Sequence(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get(index))
+                .map(|(marker, child)| (marker, child)),
+            Node::Other => None,
+        };
+        let Some((marker, child)) = child else {
+            break;
+        };
+        found = Some(Position {
+            depth: depth + 1,
+            line: marker.line(),
+            column: marker.col() + 1,
+        });
+        node = child;
+    }
+    found
+}
+
+/// Reads the node that starts with the event
+fn read_node(parser: &mut Parser<std::str::Chars>, event: Event) -> Option<Node> {
+    match event {
+        Event::MappingStart(..) => read_mapping(parser),
+        Event::SequenceStart(..) => read_sequence(parser),
+        _ => Some(Node::Other),
+    }
+}
+
+/// Reads the entries of a mapping after its start, up to and including its end
+fn read_mapping(parser: &mut Parser<std::str::Chars>) -> Option<Node> {
+    let mut entries = Vec::new();
+    loop {
+        let (event, marker) = parser.next_token().ok()?;
+        let key = match event {
+            Event::MappingEnd => return Some(Node::Mapping(entries)),
+            Event::Scalar(key, ..) => Some(key),
+            event => {
+                // A key that is a collection can't be in a key path
+                read_node(parser, event)?;
+                None
+            }
+        };
+        let (event, _) = parser.next_token().ok()?;
+        entries.push((key, marker, read_node(parser, event)?));
+    }
+}
+
+/// Reads the items of a sequence after its start, up to and including its end
+fn read_sequence(parser: &mut Parser<std::str::Chars>) -> Option<Node> {
+    let mut items = Vec::new();
+    loop {
+        let (event, marker) = parser.next_token().ok()?;
+        if event == Event::SequenceEnd {
+            return Some(Node::Sequence(items));
+        }
+        items.push((marker, read_node(parser, event)?));
+    }
+}
+
+/// A TOML table, array of tables or array being walked through
+enum TomlNode<'a> {
+    Table(&'a dyn toml_edit::TableLike),
+    Tables(&'a toml_edit::ArrayOfTables),
+    Array(&'a toml_edit::Array),
+    Other,
+}
+
+impl<'a> TomlNode<'a> {
+    fn item(item: &'a toml_edit::Item) -> Self {
+        match item {
+            toml_edit::Item::Table(table) => TomlNode::Table(table),
+            toml_edit::Item::ArrayOfTables(tables) => TomlNode::Tables(tables),
+            toml_edit::Item::Value(value) => Self::value(value),
+            toml_edit::Item::None => TomlNode::Other,
+        }
+    }
+
+    fn value(value: &'a toml_edit::Value) -> Self {
+        match value {
+            toml_edit::Value::InlineTable(table) => TomlNode::Table(table),
+            toml_edit::Value::Array(array) => TomlNode::Array(array),
+            _ => TomlNode::Other,
+        }
+    }
+}
+
+/// Finds the deepest part of the key path in a TOML document. Segments of an array or
+/// array of tables are the index of the item
+pub fn locate_toml<S: AsRef<str>>(content: &str, path: &[S]) -> Option<Position> {
+    let document = toml_edit::ImDocument::parse(content).ok()?;
+    let mut node = TomlNode::Table(document.as_table());
+    let mut found = None;
+    for (depth, segment) in path.iter().enumerate() {
+        let segment = segment.as_ref();
+        let index = segment.parse::<usize>().ok();
+        let child = match node {
+            TomlNode::Table(table) => table
+                .get_key_value(segment)
+                .map(|(key, item)| (key.span(), TomlNode::item(item))),
+            TomlNode::Tables(tables) => index
+                .and_then(|x| tables.get(x))
+                .map(|table| (table.span(), TomlNode::Table(table))),
+            TomlNode::Array(array) => index
+                .and_then(|x| array.get(x))
+                .map(|value| (value.span(), TomlNode::value(value))),
+            TomlNode::Other => None,
+        };
+        let Some((Some(span), child)) = child else {
+            break;
+        };
+        found = Some(offset_position(content, depth + 1, span.start));
+        node = child;
+    }
+    found
+}
+
+/// The line and column of the byte offset
+fn offset_position(content: &str, depth: usize, offset: usize) -> Position {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+    Position {
+        depth,
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(depth: usize, line: usize, column: usize) -> Option<Position> {
+        Some(Position {
+            depth,
+            line,
+            column,
+        })
+    }
+
+    #[test]
+    fn block_mapping_keys_are_found() {
+        let content = "types:\n  String:\n    default: String\n    formatt: {}\n";
+        assert_eq!(
+            locate_yaml(content, &["types", "String", "formatt"]),
+            at(3, 4, 5)
+        );
+    }
+
+    #[test]
+    fn block_scalars_are_not_read_as_keys() {
+        let content = "description: |\n  types: not a key\ntypes:\n  String: {}\n";
+        assert_eq!(locate_yaml(content, &["types"]), at(1, 3, 1));
+    }
+
+    #[test]
+    fn flow_mappings_and_sequence_items_are_found() {
+        let content = "overrides:\n  - { property: id, typ: Uuid }\n  - {property: name}\n";
+        assert_eq!(
+            locate_yaml(content, &["overrides", "0", "typ"]),
+            at(3, 2, 21)
+        );
+        assert_eq!(locate_yaml(content, &["overrides", "1"]), at(2, 3, 5));
+    }
+
+    #[test]
+    fn json_keys_are_found() {
+        let content = "{\n  \"types\": {\n    \"String\": {\"default\": \"String\"}\n  }\n}\n";
+        assert_eq!(
+            locate_yaml(content, &["types", "String", "default"]),
+            at(3, 3, 16)
+        );
+    }
+
+    #[test]
+    fn the_deepest_key_found_is_returned() {
+        let content = "types:\n  String:\n    default: String\n";
+        assert_eq!(locate_yaml(content, &["types", "Integer"]), at(1, 1, 1));
+        assert_eq!(locate_yaml(content, &["other"]), None);
+        assert_eq!(locate_yaml("types: [", &["types"]), None);
+    }
+
+    #[test]
+    fn toml_keys_tables_and_arrays_of_tables_are_found() {
+        let content = "arraylayout = \"x\"\n\n[types.String]\ndefault = \"String\"\n\n[[overrides]]\nproperty = \"id\"\ntyp = \"Uuid\"\n";
+        assert_eq!(locate_toml(content, &["arraylayout"]), at(1, 1, 1));
+        assert_eq!(
+            locate_toml(content, &["types", "String", "default"]),
+            at(3, 4, 1)
+        );
+        assert_eq!(
+            locate_toml(content, &["overrides", "0", "typ"]),
+            at(3, 8, 1)
+        );
+        assert_eq!(
+            locate_toml(
+                "types = { String = { defaul = \"x\" } }",
+                &["types", "String", "defaul"]
+            ),
+            at(3, 1, 22)
+        );
+    }
+}
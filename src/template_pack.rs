@@ -0,0 +1,300 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use simplelog::{debug, info, warn};
+
+use crate::{terminal_setup, TemplateInstallArgs};
+
+/// File describing a template pack, at the root of the pack
+pub const MANIFEST_FILE_NAME: &str = "pack.yaml";
+/// Partial config of a pack, merged beneath the config that uses the pack
+pub const CONFIG_FILE_NAME: &str = "config.yaml";
+/// Folder of a pack containing its templates
+pub const TEMPLATES_FOLDER_NAME: &str = "templates";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Manifest {
+    fn read(dir: &Path) -> anyhow::Result<Self> {
+        let file = dir.join(MANIFEST_FILE_NAME);
+        let content = fs::read_to_string(&file).map_err(|e| {
+            anyhow::anyhow!(
+                "Pack manifest '{}' could not be read: {}",
+                file.display(),
+                e
+            )
+        })?;
+        let manifest: Self = serde_yaml::from_str(&content).map_err(|e| {
+            anyhow::anyhow!("Failed to parse pack manifest '{}': {}", file.display(), e)
+        })?;
+        check_segment("name", &manifest.name)?;
+        check_segment("version", &manifest.version)?;
+        Ok(manifest)
+    }
+}
+
+/// Installed template pack whose templates and partial config are used beneath this config
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PackRef {
+    /// Name of the pack, from its manifest
+    #[schemars(length(min = 1))]
+    pub name: String,
+    /// Version of the pack, from its manifest
+    #[schemars(length(min = 1))]
+    pub version: String,
+}
+
+impl PackRef {
+    /// Folder the pack is installed in, or an error naming the command to install it
+    pub fn installed_path(&self) -> anyhow::Result<PathBuf> {
+        check_segment("name", &self.name)?;
+        check_segment("version", &self.version)?;
+        let dir = store_dir()?.join(&self.name).join(&self.version);
+        if dir.join(MANIFEST_FILE_NAME).is_file() {
+            Ok(dir)
+        } else {
+            Err(anyhow::anyhow!(
+                "Template pack {}@{} is not installed, use `api-gen template install <PATH>`",
+                self.name,
+                self.version
+            ))
+        }
+    }
+}
+
+/// The name and version are used as folder names in the store
+fn check_segment(key: &str, value: &str) -> anyhow::Result<()> {
+    if value.is_empty()
+        || value.starts_with('.')
+        || value.contains(|x: char| x == '/' || x == '\\' || x.is_whitespace())
+    {
+        return Err(anyhow::anyhow!(
+            "Invalid pack {} '{}', it must not be empty, start with '.' or contain slashes or spaces",
+            key,
+            value
+        ));
+    }
+    Ok(())
+}
+
+/// Folder the packs are installed in, `~/.api-gen/packs`
+pub fn store_dir() -> anyhow::Result<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".api-gen").join("packs"))
+        .ok_or_else(|| anyhow::anyhow!("Home directory not found to install template packs in"))
+}
+
+/// Installs a template pack from a directory or a `.tar.gz` archive into the local store
+pub fn install(args: TemplateInstallArgs) -> anyhow::Result<()> {
+    terminal_setup(args.quiet, args.verbose)?;
+    if !args.path.exists() {
+        return Err(anyhow::anyhow!(
+            "Template pack '{}' not found",
+            args.path.display()
+        ));
+    }
+    let store = store_dir()?;
+    fs::create_dir_all(&store)?;
+
+    // Archives are unpacked next to the installed packs so they can be moved in place
+    let staging = Staging(store.join(format!(".staging-{}", std::process::id())));
+    let source = if args.path.is_dir() {
+        args.path.clone()
+    } else {
+        info!("Unpacking {:?}", args.path);
+        unpack(&args.path, &staging.0)?;
+        pack_root(&staging.0)?
+    };
+    let manifest = install_dir(&source, &store, args.force)?;
+    info!(
+        "Installed template pack {}@{}, use it by adding `pack: {{ name: {}, version: {} }}` to the config",
+        manifest.name,
+        manifest.version,
+        manifest.name,
+        manifest.version
+    );
+    Ok(())
+}
+
+/// Folder an archive is unpacked in, removed when dropped so a failed install does not leave it
+/// in the store
+struct Staging(PathBuf);
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        if self.0.exists() {
+            if let Err(e) = fs::remove_dir_all(&self.0) {
+                warn!("Failed to remove {:?}: {}", self.0, e);
+            }
+        }
+    }
+}
+
+fn install_dir(source: &Path, store: &Path, force: bool) -> anyhow::Result<Manifest> {
+    let manifest = Manifest::read(source)?;
+    if !source.join(TEMPLATES_FOLDER_NAME).is_dir() {
+        return Err(anyhow::anyhow!(
+            "Template pack '{}' has no {} folder",
+            source.display(),
+            TEMPLATES_FOLDER_NAME
+        ));
+    }
+    let destination = store.join(&manifest.name).join(&manifest.version);
+    if destination.exists() {
+        if !force {
+            return Err(anyhow::anyhow!(
+                "Template pack {}@{} is already installed, use --force to replace it",
+                manifest.name,
+                manifest.version
+            ));
+        }
+        debug!("Removing installed pack {:?}", destination);
+        fs::remove_dir_all(&destination)?;
+    }
+    copy_dir(source, &destination)?;
+    Ok(manifest)
+}
+
+fn unpack(archive: &Path, destination: &Path) -> anyhow::Result<()> {
+    let name = archive.to_string_lossy();
+    if !(name.ends_with(".tar.gz") || name.ends_with(".tgz")) {
+        return Err(anyhow::anyhow!(
+            "Template pack '{}' must be a directory or a .tar.gz or .tgz archive",
+            archive.display()
+        ));
+    }
+    if destination.exists() {
+        fs::remove_dir_all(destination)?;
+    }
+    fs::create_dir_all(destination)?;
+    // `unpack` skips entries that would be written outside the destination
+    tar::Archive::new(GzDecoder::new(fs::File::open(archive)?))
+        .unpack(destination)
+        .map_err(|e| anyhow::anyhow!("Failed to unpack '{}': {}", archive.display(), e))
+}
+
+/// The folder of an unpacked archive with the manifest, either its root or its only folder
+fn pack_root(dir: &Path) -> anyhow::Result<PathBuf> {
+    if dir.join(MANIFEST_FILE_NAME).is_file() {
+        return Ok(dir.to_path_buf());
+    }
+    let entries = fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    match entries.as_slice() {
+        [entry] if entry.path().join(MANIFEST_FILE_NAME).is_file() => Ok(entry.path()),
+        _ => Err(anyhow::anyhow!(
+            "Template pack archive has no {} at its root",
+            MANIFEST_FILE_NAME
+        )),
+    }
+}
+
+fn copy_dir(source: &Path, destination: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the installed template packs
+pub fn list() -> anyhow::Result<()> {
+    let store = store_dir()?;
+    if !store.is_dir() {
+        println!("No template packs installed");
+        return Ok(());
+    }
+    let mut packs = Vec::new();
+    for name in fs::read_dir(&store)? {
+        let name = name?.path();
+        if !name.is_dir()
+            || name
+                .file_name()
+                .is_some_and(|x| x.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        for version in fs::read_dir(&name)? {
+            if let Ok(manifest) = Manifest::read(&version?.path()) {
+                packs.push(manifest);
+            }
+        }
+    }
+    if packs.is_empty() {
+        println!("No template packs installed");
+    }
+    packs.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    for pack in packs {
+        match pack.description {
+            Some(description) => println!("{}@{} - {}", pack.name, pack.version, description),
+            None => println!("{}@{}", pack.name, pack.version),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty folder for the test in the temp folder
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("api-gen-pack-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn the_staging_folder_is_removed_when_unpacking_fails() {
+        let dir = test_dir("staging");
+        let archive = dir.join("pack.tar.gz");
+        fs::write(&archive, "not an archive").unwrap();
+        let staging = Staging(dir.join(".staging"));
+        assert!(unpack(&archive, &staging.0).is_err());
+        assert!(staging.0.is_dir());
+        drop(staging);
+        assert!(!dir.join(".staging").exists());
+    }
+
+    #[test]
+    fn the_staging_folder_is_removed_when_the_archive_has_no_manifest() {
+        let dir = test_dir("no-manifest");
+        let archive = dir.join("pack.tar.gz");
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            fs::File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "templates/model.dart", std::io::empty())
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        let staging = Staging(dir.join(".staging"));
+        unpack(&archive, &staging.0).unwrap();
+        assert!(pack_root(&staging.0).is_err());
+        drop(staging);
+        assert!(!dir.join(".staging").exists());
+    }
+}
@@ -1,15 +1,16 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use simplelog::debug;
-use tera::{from_value, to_value, Function, Value};
+use tera::{from_value, to_value, Filter, Function, Value};
 
 use crate::{
-    config::{Config, Type},
+    config::{Config, TargetType, TypeOverrides},
+    naming,
     serde_method::{DataStructure, Int64FloatOrUsize},
 };
 use mock_json::mock;
 
-pub fn extended(extended: HashMap<String, String>) -> impl Function {
+pub fn extended(extended: HashMap<String, Value>) -> impl Function {
     Box::new(
         move |args: &HashMap<String, Value>| -> tera::Result<Value> {
             match args.get("key") {
@@ -23,7 +24,7 @@ pub fn extended(extended: HashMap<String, String>) -> impl Function {
     )
 }
 
-pub fn exists(extended: HashMap<String, String>) -> impl Function {
+pub fn exists(extended: HashMap<String, Value>) -> impl Function {
     Box::new(
         move |args: &HashMap<String, Value>| -> tera::Result<Value> {
             match args.get("key") {
@@ -37,86 +38,31 @@ pub fn exists(extended: HashMap<String, String>) -> impl Function {
     )
 }
 
-pub fn map_type(types: HashMap<String, Type>) -> impl Function {
-    Box::new(
-        move |args: &HashMap<String, Value>| -> tera::Result<Value> {
-            match (args.get("type"), args.get("format")) {
-                (None, None) => Err("Expect type to be set".into()),
-                (None, Some(_)) => Err("Expect type to be set".into()),
-                (Some(type_name), None) => match from_value::<String>(type_name.clone()) {
-                    Ok(v) => Ok(to_value(&types.get(&v).unwrap().default).unwrap()),
-                    Err(_) => Err("oops".into()),
-                },
-                (Some(type_name), Some(format)) => {
-                    if !format.is_null() {
-                        match (
-                            from_value::<String>(type_name.clone()),
-                            from_value::<String>(format.clone()),
-                        ) {
-                            (Ok(type_name), Ok(format)) => Ok(to_value(
-                                types
-                                    .get(&type_name)
-                                    .unwrap()
-                                    .format
-                                    .as_ref()
-                                    .unwrap()
-                                    .get(&format)
-                                    .unwrap(),
-                            )
-                            .unwrap()),
-                            (Ok(_), Err(_)) => Err("Failed to parse value".into()),
-                            (Err(_), Ok(_)) => Err("Failed to parse value".into()),
-                            (Err(_), Err(_)) => Err("Failed to parse value".into()),
-                            // Ok(v) => Ok(to_value(types.types.get(&v).unwrap().default).unwrap()),
-                            // Err(_) => Err("oops".into()),
-                        }
-                    } else {
-                        match from_value::<String>(type_name.clone()) {
-                            Ok(v) => Ok(to_value(&types.get(&v).unwrap().default).unwrap()),
-                            Err(_) => Err("oops".into()),
-                        }
-                    }
-                }
-            }
-        },
-    )
-}
-
 // TODO determine if better way (preprocess the mapped types)
-pub fn map_type_new(config: Config) -> impl Function {
+pub fn map_type_new(config: Arc<Config>) -> impl Function {
     Box::new(
         move |args: &HashMap<String, Value>| -> tera::Result<Value> {
             match args.get("type") {
                 None => Err("Expect type to be set".into()),
                 Some(type_name) => match from_value::<DataStructure>(type_name.clone()) {
                     Ok(v) => {
-                        let openapi_type = if let Some(t) = v.object_name {
+                        if let Some(t) = &v.object_name {
                             debug!("Array {}", t);
-                            t
-                        } else {
-                            v.property_type.clone()
-                        };
+                        }
                         debug!("Property Type {}", v.property_type);
-                        let resulting_type = match v.format {
-                            Some(format) => config
-                                .types
-                                .get(&openapi_type)
-                                .unwrap()
-                                .format
-                                .as_ref()
-                                .unwrap()
-                                .get(&format)
-                                .unwrap(),
-                            None => &config.types.get(&v.property_type).unwrap().default,
-                        };
-                        if v.property_type == "Array" {
-                            debug!("Resulting Array {}", resulting_type);
-                            return Ok(to_value(
-                                config.array_layout.replace("{type}", resulting_type),
+                        match mapped_type(&v, &config) {
+                            Some(mapped) => {
+                                if v.property_type == "Array" {
+                                    debug!("Resulting Array {}", mapped);
+                                }
+                                Ok(to_value(optional_layout(&v, &config, mapped)).unwrap())
+                            }
+                            None => Err(format!(
+                                "The type '{}' of '{}' is not in the config types",
+                                v.property_type, v.name
                             )
-                            .unwrap());
+                            .into()),
                         }
-                        Ok(to_value(resulting_type).unwrap())
                     }
                     Err(_) => Err("oops".into()),
                 },
@@ -125,7 +71,142 @@ pub fn map_type_new(config: Config) -> impl Function {
     )
 }
 
-pub fn json_typing(config: Config) -> impl Function {
+/// Maps the type with the config, before the optional or nullable layout is applied
+pub(crate) fn mapped_type(v: &DataStructure, config: &Config) -> Option<String> {
+    resolve_type(v, config).map(|x| x.name)
+}
+
+/// What a model using a type has to import
+#[derive(Debug, PartialEq)]
+pub(crate) enum TypeImport<'a> {
+    /// An import from the config or from a pinned type
+    Path(String),
+    /// The model of an object, imported from its own file
+    Model(&'a DataStructure),
+}
+
+/// A type mapped with the config with what it needs to be imported
+#[derive(Debug, PartialEq)]
+pub(crate) struct ResolvedType<'a> {
+    pub name: String,
+    pub imports: Vec<TypeImport<'a>>,
+}
+
+impl ResolvedType<'_> {
+    fn new(name: &str, import: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            imports: import
+                .map(|x| TypeImport::Path(x.to_string()))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn target(target: &TargetType) -> Self {
+        Self::new(target.target(), target.import())
+    }
+
+    fn array(mut self, config: &Config) -> Self {
+        self.name = config.array_layout.replace("{type}", &self.name);
+        self
+    }
+}
+
+/// Maps the type with the config, before the optional or nullable layout is applied. Objects
+/// are mapped to their model name and arrays to the `arrayLayout` of their item, a format
+/// without a mapping uses the default of the type. The imports are those of the entry the type
+/// comes from
+pub(crate) fn resolve_type<'a>(v: &'a DataStructure, config: &Config) -> Option<ResolvedType<'a>> {
+    if let Some(pinned) = pinned_type(v, config) {
+        return Some(ResolvedType::target(&pinned));
+    }
+    if v.property_type == "Array" {
+        if let Some(pinned) = v.properties.first().and_then(|x| pinned_type(x, config)) {
+            return Some(ResolvedType::target(&pinned).array(config));
+        }
+    }
+    if let Some(type_override) = &v.type_override {
+        let import = config
+            .overrides
+            .iter()
+            .find(|x| &x.target == type_override)
+            .and_then(|x| x.import.as_deref());
+        return Some(ResolvedType::new(type_override, import));
+    }
+    match v.property_type.as_str() {
+        "Array" => {
+            let item = match v.properties.first() {
+                Some(item) => resolve_type(item, config)?,
+                None => ResolvedType::new(v.object_name.as_ref()?, None),
+            };
+            Some(item.array(config))
+        }
+        "Object" if v.object_name.is_some() => Some(ResolvedType {
+            name: v.object_name.clone()?,
+            imports: vec![TypeImport::Model(v)],
+        }),
+        _ => {
+            let mapping = config.types.get(&v.property_type)?;
+            let formatted = v
+                .format
+                .as_ref()
+                .and_then(|format| mapping.format.as_ref()?.get(format));
+            Some(match formatted {
+                Some(target) => ResolvedType::target(target),
+                None => ResolvedType::new(&mapping.default, mapping.import.as_deref()),
+            })
+        }
+    }
+}
+
+/// Wraps the type in the `optionalLayout` if the property is not required, otherwise in the
+/// `nullableLayout` if it is nullable
+pub(crate) fn optional_layout(v: &DataStructure, config: &Config, mapped: String) -> String {
+    if v.is_root {
+        return mapped;
+    }
+    let layout = config
+        .optional_layout
+        .as_ref()
+        .filter(|_| !v.required)
+        .or(config.nullable_layout.as_ref().filter(|_| v.nullable));
+    match layout {
+        Some(layout) => layout.replace("{type}", &mapped),
+        None => mapped,
+    }
+}
+
+/// The type pinned by the `typeExtension` vendor extension of the schema
+fn pinned_type(data_structure: &DataStructure, config: &Config) -> Option<TargetType> {
+    config
+        .type_extension_key()
+        .and_then(|extension| data_structure.extensions.get(&extension))
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+}
+
+pub fn identifier(config: Arc<Config>) -> impl Filter {
+    move |value: &Value, _: &HashMap<String, Value>| -> tera::Result<Value> {
+        let name = from_value::<String>(value.clone())?;
+        Ok(to_value(naming::identifier(&name, &config.naming)).unwrap())
+    }
+}
+
+pub fn field_name(config: Arc<Config>) -> impl Filter {
+    move |value: &Value, _: &HashMap<String, Value>| -> tera::Result<Value> {
+        let name = from_value::<String>(value.clone())?;
+        Ok(to_value(naming::field_name(&name, &config.naming)).unwrap())
+    }
+}
+
+pub fn type_name(config: Arc<Config>) -> impl Filter {
+    move |value: &Value, _: &HashMap<String, Value>| -> tera::Result<Value> {
+        let name = from_value::<String>(value.clone())?;
+        Ok(to_value(naming::type_name(&name, &config.naming)).unwrap())
+    }
+}
+
+pub fn json_typing(config: Arc<Config>) -> impl Function {
     Box::new(
         move |args: &HashMap<String, Value>| -> tera::Result<Value> {
             match args.get("structure") {
@@ -146,6 +227,9 @@ pub fn json_typing(config: Config) -> impl Function {
 }
 
 fn data_structure_to_json(data_structure: &DataStructure, config: &Config) -> serde_json::Value {
+    if let Some(type_override) = &data_structure.type_override {
+        return serde_json::Value::String(type_override.clone());
+    }
     match data_structure.property_type.as_str() {
         "Object" => {
             let mut map = serde_json::Map::new();
@@ -199,13 +283,14 @@ fn property_to_type(property_type: &String, format: &Option<String>, config: &Co
             .unwrap()
             .get(format.as_str())
             .unwrap()
+            .target()
             .to_string(),
         None => config.types.get(property_type).unwrap().default.to_string(),
     };
     to_value(resulting_type).unwrap()
 }
 
-pub fn json_value(config: Config) -> impl Function {
+pub fn json_value(overrides: Arc<TypeOverrides>) -> impl Function {
     Box::new(
         move |args: &HashMap<String, Value>| -> tera::Result<Value> {
             match args.get("structure") {
@@ -213,7 +298,8 @@ pub fn json_value(config: Config) -> impl Function {
                 Some(type_name) => match from_value::<DataStructure>(type_name.clone()) {
                     Ok(v) => {
                         if v.is_root {
-                            let sample_json = data_structure_to_json_with_value(&v, &config);
+                            let sample_json =
+                                data_structure_to_json_with_value(&v, &overrides, &[]);
                             Ok(mock(&sample_json))
                         } else {
                             Err("Expected the root object".into())
@@ -226,17 +312,38 @@ pub fn json_value(config: Config) -> impl Function {
     )
 }
 
+/// The sample value of the override matching the property, where `models` are the names of the
+/// model it is in
+fn sample_override(
+    data_structure: &DataStructure,
+    overrides: &TypeOverrides,
+    models: &[&str],
+) -> Option<serde_json::Value> {
+    if data_structure.is_root {
+        return None;
+    }
+    overrides
+        .find_rule(&data_structure.name, models)?
+        .sample
+        .clone()
+}
+
 fn data_structure_to_json_with_value(
     data_structure: &DataStructure,
-    config: &Config,
+    overrides: &TypeOverrides,
+    models: &[&str],
 ) -> serde_json::Value {
+    if let Some(sample) = sample_override(data_structure, overrides, models) {
+        return sample;
+    }
+    let names = data_structure.model_names(models);
     match data_structure.property_type.as_str() {
         "Object" => {
             let mut map = serde_json::Map::new();
             for property in &data_structure.properties {
                 map.insert(
                     property.name.clone(),
-                    data_structure_to_json_with_value(property, config),
+                    data_structure_to_json_with_value(property, overrides, &names),
                 );
             }
             serde_json::Value::Object(map)
@@ -244,7 +351,8 @@ fn data_structure_to_json_with_value(
         "Array" => {
             let vec = vec![data_structure_to_json_with_value(
                 &data_structure.properties[0],
-                config,
+                overrides,
+                &names,
             )];
             serde_json::Value::Array(vec)
         }
@@ -264,11 +372,11 @@ fn data_structure_to_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Float(std::f32::MIN as f64));
+                        .unwrap_or(Int64FloatOrUsize::Float(f32::MIN as f64));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Float(std::f32::MAX as f64));
+                        .unwrap_or(Int64FloatOrUsize::Float(f32::MAX as f64));
                     match (min, max) {
                         (Int64FloatOrUsize::Float(min), Int64FloatOrUsize::Float(max)) => {
                             serde_json::Value::String(format!("@Float|{}~{}", min, max))
@@ -280,11 +388,11 @@ fn data_structure_to_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MIN));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MIN));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MAX));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MAX));
                     match (min, max) {
                         (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                             serde_json::Value::String(format!("@Float|{}~{}", min, max))
@@ -298,11 +406,11 @@ fn data_structure_to_json_with_value(
                 let min = data_structure
                     .min
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Float(std::f32::MIN as f64));
+                    .unwrap_or(Int64FloatOrUsize::Float(f32::MIN as f64));
                 let max: Int64FloatOrUsize = data_structure
                     .max
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Float(std::f32::MAX as f64));
+                    .unwrap_or(Int64FloatOrUsize::Float(f32::MAX as f64));
                 match (min, max) {
                     (Int64FloatOrUsize::Float(min), Int64FloatOrUsize::Float(max)) => {
                         serde_json::Value::String(format!("@Float|{}~{}", min, max))
@@ -317,11 +425,11 @@ fn data_structure_to_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i32::MIN as i64));
+                        .unwrap_or(Int64FloatOrUsize::Int(i32::MIN as i64));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i32::MAX as i64));
+                        .unwrap_or(Int64FloatOrUsize::Int(i32::MAX as i64));
                     match (min, max) {
                         (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                             serde_json::Value::String(format!("@Number|{}~{}", min, max))
@@ -333,11 +441,11 @@ fn data_structure_to_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MIN));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MIN));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MAX));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MAX));
                     match (min, max) {
                         (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                             serde_json::Value::String(format!("@Number|{}~{}", min, max))
@@ -351,11 +459,11 @@ fn data_structure_to_json_with_value(
                 let min = data_structure
                     .min
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Int(std::i32::MIN as i64));
+                    .unwrap_or(Int64FloatOrUsize::Int(i32::MIN as i64));
                 let max: Int64FloatOrUsize = data_structure
                     .max
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Int(std::i32::MAX as i64));
+                    .unwrap_or(Int64FloatOrUsize::Int(i32::MAX as i64));
                 match (min, max) {
                     (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                         serde_json::Value::String(format!("@Number|{}~{}", min, max))
@@ -369,7 +477,7 @@ fn data_structure_to_json_with_value(
     }
 }
 
-pub fn json_minimum_value(config: Config) -> impl Function {
+pub fn json_minimum_value(overrides: Arc<TypeOverrides>) -> impl Function {
     Box::new(
         move |args: &HashMap<String, Value>| -> tera::Result<Value> {
             match args.get("structure") {
@@ -378,7 +486,7 @@ pub fn json_minimum_value(config: Config) -> impl Function {
                     Ok(v) => {
                         if v.is_root {
                             let sample_json =
-                                data_structure_to_minimum_json_with_value(&v, &config);
+                                data_structure_to_minimum_json_with_value(&v, &overrides, &[]);
                             Ok(mock(&sample_json))
                         } else {
                             Err("Expected the root object".into())
@@ -393,11 +501,16 @@ pub fn json_minimum_value(config: Config) -> impl Function {
 
 fn data_structure_to_minimum_json_with_value(
     data_structure: &DataStructure,
-    config: &Config,
+    overrides: &TypeOverrides,
+    models: &[&str],
 ) -> serde_json::Value {
     if !data_structure.required {
         return serde_json::Value::Null;
     }
+    if let Some(sample) = sample_override(data_structure, overrides, models) {
+        return sample;
+    }
+    let names = data_structure.model_names(models);
     match data_structure.property_type.as_str() {
         "Object" => {
             let mut map = serde_json::Map::new();
@@ -405,7 +518,7 @@ fn data_structure_to_minimum_json_with_value(
                 if property.required {
                     map.insert(
                         property.name.clone(),
-                        data_structure_to_minimum_json_with_value(property, config),
+                        data_structure_to_minimum_json_with_value(property, overrides, &names),
                     );
                 }
             }
@@ -414,7 +527,8 @@ fn data_structure_to_minimum_json_with_value(
         "Array" => {
             let vec = vec![data_structure_to_minimum_json_with_value(
                 &data_structure.properties[0],
-                config,
+                overrides,
+                &names,
             )];
             serde_json::Value::Array(vec)
         }
@@ -434,11 +548,11 @@ fn data_structure_to_minimum_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Float(std::f32::MIN as f64));
+                        .unwrap_or(Int64FloatOrUsize::Float(f32::MIN as f64));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Float(std::f32::MAX as f64));
+                        .unwrap_or(Int64FloatOrUsize::Float(f32::MAX as f64));
                     match (min, max) {
                         (Int64FloatOrUsize::Float(min), Int64FloatOrUsize::Float(max)) => {
                             serde_json::Value::String(format!("@Float|{}~{}", min, max))
@@ -450,11 +564,11 @@ fn data_structure_to_minimum_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MIN));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MIN));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MAX));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MAX));
                     match (min, max) {
                         (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                             serde_json::Value::String(format!("@Float|{}~{}", min, max))
@@ -468,11 +582,11 @@ fn data_structure_to_minimum_json_with_value(
                 let min = data_structure
                     .min
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Float(std::f32::MIN as f64));
+                    .unwrap_or(Int64FloatOrUsize::Float(f32::MIN as f64));
                 let max: Int64FloatOrUsize = data_structure
                     .max
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Float(std::f32::MAX as f64));
+                    .unwrap_or(Int64FloatOrUsize::Float(f32::MAX as f64));
                 match (min, max) {
                     (Int64FloatOrUsize::Float(min), Int64FloatOrUsize::Float(max)) => {
                         serde_json::Value::String(format!("@Float|{}~{}", min, max))
@@ -487,11 +601,11 @@ fn data_structure_to_minimum_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i32::MIN as i64));
+                        .unwrap_or(Int64FloatOrUsize::Int(i32::MIN as i64));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i32::MAX as i64));
+                        .unwrap_or(Int64FloatOrUsize::Int(i32::MAX as i64));
                     match (min, max) {
                         (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                             serde_json::Value::String(format!("@Number|{}~{}", min, max))
@@ -503,11 +617,11 @@ fn data_structure_to_minimum_json_with_value(
                     let min = data_structure
                         .min
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MIN));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MIN));
                     let max: Int64FloatOrUsize = data_structure
                         .max
                         .clone()
-                        .unwrap_or(Int64FloatOrUsize::Int(std::i64::MAX));
+                        .unwrap_or(Int64FloatOrUsize::Int(i64::MAX));
                     match (min, max) {
                         (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                             serde_json::Value::String(format!("@Number|{}~{}", min, max))
@@ -521,11 +635,11 @@ fn data_structure_to_minimum_json_with_value(
                 let min = data_structure
                     .min
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Int(std::i32::MIN as i64));
+                    .unwrap_or(Int64FloatOrUsize::Int(i32::MIN as i64));
                 let max: Int64FloatOrUsize = data_structure
                     .max
                     .clone()
-                    .unwrap_or(Int64FloatOrUsize::Int(std::i32::MAX as i64));
+                    .unwrap_or(Int64FloatOrUsize::Int(i32::MAX as i64));
                 match (min, max) {
                     (Int64FloatOrUsize::Int(min), Int64FloatOrUsize::Int(max)) => {
                         serde_json::Value::String(format!("@Number|{}~{}", min, max))
@@ -538,3 +652,132 @@ fn data_structure_to_minimum_json_with_value(
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::TypeOverride;
+
+    use super::*;
+
+    fn field(name: &str, property_type: &str) -> DataStructure {
+        DataStructure {
+            name: name.to_string(),
+            property_type: property_type.to_string(),
+            required: true,
+            ..Default::default()
+        }
+    }
+
+    fn order() -> DataStructure {
+        let mut customer = field("customer", "Object");
+        customer.object_name = Some("Customer".to_string());
+        customer.properties = vec![field("id", "Integer")];
+        let mut order = field("Order", "Object");
+        order.object_name = Some("Order".to_string());
+        order.is_root = true;
+        order.properties = vec![field("id", "Integer"), customer];
+        order
+    }
+
+    fn sample_override(model: Option<&str>, sample: serde_json::Value) -> TypeOverrides {
+        TypeOverrides::new(&[TypeOverride {
+            property: Some("id".to_string()),
+            pattern: None,
+            model: model.map(String::from),
+            target: "OrderId".to_string(),
+            import: None,
+            sample: Some(sample),
+        }])
+        .unwrap()
+    }
+
+    fn config() -> Config {
+        serde_yaml::from_str(
+            r#"
+arrayLayout: List<{type}>
+target: dart
+types:
+  String:
+    default: String
+    import: package:app/string.dart
+    format:
+      DateTime: DateTime
+      decimal: { type: Decimal, import: package:decimal/decimal.dart }
+overrides:
+  - property: id
+    type: OrderId
+    import: package:app/ids.dart
+"#,
+        )
+        .unwrap()
+    }
+
+    fn imports(resolved: Option<ResolvedType>) -> Vec<String> {
+        resolved
+            .unwrap()
+            .imports
+            .into_iter()
+            .map(|x| match x {
+                TypeImport::Path(path) => path,
+                TypeImport::Model(model) => format!("model {}", model.name),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn imports_come_from_the_entry_the_type_is_mapped_from() {
+        let config = config();
+        let mut string = field("name", "String");
+        assert_eq!(
+            imports(resolve_type(&string, &config)),
+            ["package:app/string.dart"]
+        );
+        string.format = Some("DateTime".to_string());
+        assert!(imports(resolve_type(&string, &config)).is_empty());
+        string.format = Some("decimal".to_string());
+        let resolved = resolve_type(&string, &config).unwrap();
+        assert_eq!(resolved.name, "Decimal");
+        assert_eq!(imports(Some(resolved)), ["package:decimal/decimal.dart"]);
+        string.type_override = Some("OrderId".to_string());
+        assert_eq!(
+            imports(resolve_type(&string, &config)),
+            ["package:app/ids.dart"]
+        );
+    }
+
+    #[test]
+    fn pinned_types_and_array_items_have_their_imports() {
+        let config = config();
+        let mut price = field("price", "Number");
+        price.extensions.insert(
+            "x-dart-type".to_string(),
+            serde_json::json!({ "type": "Money", "import": "package:app/money.dart" }),
+        );
+        let mut prices = field("prices", "Array");
+        prices.properties = vec![price];
+        let resolved = resolve_type(&prices, &config).unwrap();
+        assert_eq!(resolved.name, "List<Money>");
+        assert_eq!(imports(Some(resolved)), ["package:app/money.dart"]);
+        let mut orders = field("orders", "Array");
+        orders.properties = vec![order()];
+        let resolved = resolve_type(&orders, &config).unwrap();
+        assert_eq!(resolved.name, "List<Order>");
+        assert_eq!(imports(Some(resolved)), ["model Order"]);
+    }
+
+    #[test]
+    fn json_values_use_the_sample_of_the_override() {
+        let overrides = sample_override(Some("Order"), serde_json::json!("order-1"));
+        let value = data_structure_to_json_with_value(&order(), &overrides, &[]);
+        assert_eq!(value["id"], "order-1");
+        assert_eq!(value["customer"]["id"], "@Number|-2147483648~2147483647");
+    }
+
+    #[test]
+    fn minimum_json_values_use_the_sample_of_the_override() {
+        let overrides = sample_override(None, serde_json::json!(7));
+        let value = data_structure_to_minimum_json_with_value(&order(), &overrides, &[]);
+        assert_eq!(value["id"], 7);
+        assert_eq!(value["customer"]["id"], 7);
+    }
+}